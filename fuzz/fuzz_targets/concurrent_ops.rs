@@ -0,0 +1,137 @@
+//! Runs arbitrary sequences of cache operations across threads and checks that
+//! every guard refers to the requested key and that the cache is left in a
+//! consistent state.
+//!
+//! Run with `cargo fuzz run concurrent_ops` from the repository root.
+
+#![no_main]
+
+use std::{
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use arbitrary::Arbitrary;
+use granular_cache::{Cache, CacheStrategy};
+use libfuzzer_sys::fuzz_target;
+
+const CAPACITY: usize = 4;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Read(u8),
+    Write(u8),
+    Batch(Vec<u8>),
+    Invalidate(u8),
+    FailNextLoad,
+}
+
+/// Makes the next load fail, whichever thread performs it.
+static FAIL_NEXT: AtomicBool = AtomicBool::new(false);
+
+/// A strategy which loads `(key, version)` pairs and fails on demand.
+#[derive(Default)]
+struct ScriptedStrategy {
+    version: u32,
+}
+
+impl CacheStrategy for ScriptedStrategy {
+    type Key = u8;
+    type Val = (u8, u32);
+    type Err = ();
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        if FAIL_NEXT.swap(false, Ordering::SeqCst) {
+            return Err(());
+        }
+        self.version += 1;
+        Ok((*key, self.version))
+    }
+
+    fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+        val.0 != *key
+    }
+}
+
+/// Maps each key to `key % CAPACITY`, so that collisions are frequent.
+#[derive(Clone, Default)]
+struct IdentityHashBuilder;
+
+impl BuildHasher for IdentityHashBuilder {
+    type Hasher = IdentityHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        IdentityHasher(0)
+    }
+}
+
+struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 << 8) | u64::from(*b);
+        }
+    }
+}
+
+type TestCache = Cache<ScriptedStrategy, IdentityHashBuilder>;
+
+fn run(c: &TestCache, ops: &[Op]) {
+    for op in ops {
+        match op {
+            Op::Read(key) => {
+                if let Ok(val) = c.read(key) {
+                    assert_eq!(val.0, *key);
+                }
+            }
+            Op::Write(key) => {
+                if let Ok(mut val) = c.write(key) {
+                    assert_eq!(val.0, *key);
+                    val.1 += 1;
+                }
+            }
+            Op::Batch(keys) => {
+                // A batch holds all of its write locks at once, so keys must be
+                // visited in slot order and at most once per slot so as not to
+                // deadlock against itself or other threads.
+                let mut keys = keys.clone();
+                keys.sort_by_key(|k| usize::from(*k) % CAPACITY);
+                keys.dedup_by_key(|k| usize::from(*k) % CAPACITY);
+
+                let mut wb = c.write_batch();
+                for key in &keys {
+                    let _ = wb.write(key, |val| {
+                        assert_eq!(val.0, *key);
+                        val.1 += 1;
+                    });
+                }
+                wb.flush_all(|_| Ok::<_, ()>(())).unwrap();
+            }
+            Op::Invalidate(key) => {
+                if let Some(val) = c.invalidate(key) {
+                    assert_eq!(val.0, *key);
+                }
+            }
+            Op::FailNextLoad => FAIL_NEXT.store(true, Ordering::SeqCst),
+        }
+    }
+}
+
+fuzz_target!(|threads: Vec<Vec<Op>>| {
+    FAIL_NEXT.store(false, Ordering::SeqCst);
+    let c = TestCache::new::<CAPACITY>(ScriptedStrategy::default());
+
+    thread::scope(|s| {
+        for ops in threads.iter().take(4) {
+            s.spawn(|| run(&c, ops));
+        }
+    });
+
+    c.check_invariants();
+});
@@ -0,0 +1,153 @@
+#ifndef GRANULAR_CACHE_H
+#define GRANULAR_CACHE_H
+
+/* Generated by cbindgen. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The operation succeeded.
+ */
+#define GRANULAR_OK 0
+
+/**
+ * A required pointer argument was null.
+ */
+#define GRANULAR_ERR_NULL -1
+
+/**
+ * The write callback asked for the value to be discarded.
+ */
+#define GRANULAR_ERR_ABORTED -2
+
+/**
+ * A callback failed with a negative code, which is reserved for the cache's
+ * own errors.
+ */
+#define GRANULAR_ERR_CALLBACK -3
+
+/**
+ * An opaque handle to a byte-keyed cache.
+ */
+typedef struct GranularCache GranularCache;
+
+/**
+ * An opaque, growable byte buffer holding a cached value.
+ */
+typedef struct GranularValue GranularValue;
+
+/**
+ * Loads the value for the given key into `out`. Must return [`GRANULAR_OK`]
+ * on success; positive values are forwarded to the caller as is, and
+ * negative ones are reported as [`GRANULAR_ERR_CALLBACK`].
+ *
+ * The loader runs while the cache is locked for loads, so it must not operate
+ * the same cache, which deadlocks.
+ */
+typedef int32_t (*GranularLoadFn)(void *ctx, const uint8_t *key, size_t key_len, GranularValue *out);
+
+/**
+ * Inspects a value while the entry's read lock is held.
+ */
+typedef void (*GranularReadFn)(void *ctx, const uint8_t *val, size_t val_len);
+
+/**
+ * Modifies a value while the entry's write lock is held. Returning anything
+ * other than [`GRANULAR_OK`] discards the value; positive values and
+ * [`GRANULAR_ERR_ABORTED`] are forwarded to the caller as is, and other
+ * negative ones are reported as [`GRANULAR_ERR_CALLBACK`].
+ */
+typedef int32_t (*GranularWriteFn)(void *ctx, GranularValue *val);
+
+/**
+ * Creates a new cache with `capacity` slots, which must be non-zero, loading
+ * missing values through `load`. Returns null if `capacity` is zero.
+ *
+ * # Safety
+ *
+ * `ctx` is passed to `load` as is and must be usable from every thread that
+ * operates the returned cache.
+ */
+GranularCache *granular_cache_new(size_t capacity, GranularLoadFn load, void *ctx);
+
+/**
+ * Destroys a cache created by [`granular_cache_new`]. Null is a no-op.
+ *
+ * # Safety
+ *
+ * `cache` must have been returned by [`granular_cache_new`], must not be used
+ * afterwards and no other thread may be operating it.
+ */
+void granular_cache_free(GranularCache *cache);
+
+/**
+ * Acquires the value for the given key for read, loading it if needed, and
+ * passes it to `read` while the read lock is held.
+ *
+ * # Safety
+ *
+ * `cache` must be a live cache handle and `key` must be valid for reads of
+ * `key_len` bytes.
+ */
+int32_t granular_cache_read(const GranularCache *cache,
+                            const uint8_t *key,
+                            size_t key_len,
+                            GranularReadFn read,
+                            void *ctx);
+
+/**
+ * Acquires the value for the given key for write, loading it if needed, and
+ * passes it to `write` while the write lock is held.
+ *
+ * If `write` fails, the entry is discarded under the same write lock, so that
+ * no reader sees the value it may have left half-modified, and the next
+ * access reloads it.
+ *
+ * # Safety
+ *
+ * `cache` must be a live cache handle and `key` must be valid for reads of
+ * `key_len` bytes.
+ */
+int32_t granular_cache_write(const GranularCache *cache,
+                             const uint8_t *key,
+                             size_t key_len,
+                             GranularWriteFn write,
+                             void *ctx);
+
+/**
+ * Drops the value for the given key, if resident. Returns [`GRANULAR_OK`]
+ * whether or not the key was resident.
+ *
+ * # Safety
+ *
+ * `cache` must be a live cache handle and `key` must be valid for reads of
+ * `key_len` bytes.
+ */
+int32_t granular_cache_invalidate(const GranularCache *cache, const uint8_t *key, size_t key_len);
+
+/**
+ * Stores a pointer to the value's bytes in `data` and their length in `len`.
+ *
+ * # Safety
+ *
+ * `val` must be a value handed out by the cache, and `data` and `len` must
+ * be valid for writes. The stored pointer is only valid until the value is
+ * modified.
+ */
+int32_t granular_value_data(const GranularValue *val, const uint8_t **data, size_t *len);
+
+/**
+ * Replaces the value's bytes with a copy of the given ones.
+ *
+ * # Safety
+ *
+ * `val` must be a value handed out by the cache and `data` must be valid for
+ * reads of `len` bytes.
+ */
+int32_t granular_value_set(GranularValue *val, const uint8_t *data, size_t len);
+
+#endif /* GRANULAR_CACHE_H */
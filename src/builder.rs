@@ -0,0 +1,312 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    time::Duration,
+};
+
+use crate::{
+    compose::Composed,
+    occupancy::OccupancyCallback,
+    shed::{LoadQueue, ShedPolicy},
+    write_batch::BatchConfig,
+    Cache, CacheStrategy, DuplicateWrites, FlushMode, Overloaded,
+};
+
+/// A builder for a [`Cache`], for when the constructors' arguments don't
+/// cover the needed configuration.
+///
+/// The strategy and capacity are required, so they are taken up front; the
+/// hasher defaults to [`RandomState`], and every other setting to the same
+/// default as for a cache built through one of its constructors.
+pub struct CacheBuilder<S, H = RandomState>
+where
+    S: CacheStrategy,
+{
+    strategy: S,
+    capacity: usize,
+    hasher: H,
+    occupancy_watermarks: Vec<(f64, OccupancyCallback)>,
+    shedding: Option<ShedPolicy<S::Err>>,
+    warm_on_build: bool,
+    trace_sampling: Option<u32>,
+    event_journal_capacity: usize,
+    load_budget: Option<Duration>,
+    duplicate_writes: DuplicateWrites,
+    flush_mode: FlushMode,
+}
+
+impl<S> CacheBuilder<S>
+where
+    S: CacheStrategy,
+{
+    /// Constructs a new builder for a cache over the given strategy, with the
+    /// given number of slots.
+    pub fn new(strategy: S, capacity: usize) -> CacheBuilder<S> {
+        CacheBuilder {
+            strategy,
+            capacity,
+            hasher: RandomState::new(),
+            occupancy_watermarks: Vec::new(),
+            shedding: None,
+            warm_on_build: true,
+            trace_sampling: None,
+            event_journal_capacity: 0,
+            load_budget: None,
+            duplicate_writes: DuplicateWrites::default(),
+            flush_mode: FlushMode::default(),
+        }
+    }
+}
+
+impl<L, V> CacheBuilder<Composed<L, V>>
+where
+    Composed<L, V>: CacheStrategy,
+{
+    /// Constructs a new builder for a cache over the strategy composed of the
+    /// given loader and of `V` (see [`Composed`]), with the given number of
+    /// slots.
+    pub fn composed(loader: L, capacity: usize) -> CacheBuilder<Composed<L, V>> {
+        CacheBuilder::new(Composed::new(loader), capacity)
+    }
+}
+
+impl<S, H> CacheBuilder<S, H>
+where
+    S: CacheStrategy,
+{
+    /// Sets the hasher which maps keys to slots.
+    pub fn hasher<H2>(self, hasher: H2) -> CacheBuilder<S, H2> {
+        CacheBuilder {
+            strategy: self.strategy,
+            capacity: self.capacity,
+            hasher,
+            occupancy_watermarks: self.occupancy_watermarks,
+            shedding: self.shedding,
+            warm_on_build: self.warm_on_build,
+            trace_sampling: self.trace_sampling,
+            event_journal_capacity: self.event_journal_capacity,
+            load_budget: self.load_budget,
+            duplicate_writes: self.duplicate_writes,
+            flush_mode: self.flush_mode,
+        }
+    }
+
+    /// Sets whether the strategy's initial keys (see
+    /// [`CacheStrategy::initial_keys`]) are preloaded by
+    /// [`CacheBuilder::build`]. They are by default; otherwise, they may still
+    /// be preloaded later with [`Cache::warm`].
+    pub fn warm_on_build(mut self, warm: bool) -> CacheBuilder<S, H> {
+        self.warm_on_build = warm;
+        self
+    }
+
+    /// Sets the trace sampling rate, as [`Cache::set_trace_sampling`] does.
+    pub fn trace_sampling(mut self, every: u32) -> CacheBuilder<S, H> {
+        self.trace_sampling = Some(every);
+        self
+    }
+
+    /// Sets the event journal capacity, as
+    /// [`Cache::set_event_journal_capacity`] does. Events of the preloaded
+    /// initial keys are then recorded as well.
+    pub fn event_journal_capacity(mut self, capacity: usize) -> CacheBuilder<S, H> {
+        self.event_journal_capacity = capacity;
+        self
+    }
+
+    /// Sets the load budget, as [`Cache::set_load_budget`] does.
+    pub fn load_budget(mut self, budget: Duration) -> CacheBuilder<S, H> {
+        self.load_budget = Some(budget);
+        self
+    }
+
+    /// Sets what the cache's write batches do when a key is written more than
+    /// once, unless overridden with [`WriteBatch::set_duplicate_writes`].
+    ///
+    /// [`WriteBatch::set_duplicate_writes`]: crate::WriteBatch::set_duplicate_writes
+    pub fn duplicate_writes(mut self, policy: DuplicateWrites) -> CacheBuilder<S, H> {
+        self.duplicate_writes = policy;
+        self
+    }
+
+    /// Sets which entries the cache's write batches flush with
+    /// [`WriteBatch::flush`], unless overridden with
+    /// [`WriteBatch::set_flush_mode`].
+    ///
+    /// [`WriteBatch::flush`]: crate::WriteBatch::flush
+    /// [`WriteBatch::set_flush_mode`]: crate::WriteBatch::set_flush_mode
+    pub fn flush_mode(mut self, mode: FlushMode) -> CacheBuilder<S, H> {
+        self.flush_mode = mode;
+        self
+    }
+
+    /// Registers a callback to be called with the number of occupied slots
+    /// once more than `fraction` of the slots hold a value. The callback is
+    /// debounced: it isn't called again until occupancy falls back to the
+    /// watermark or below.
+    ///
+    /// The callback runs while a slot is locked for write, so it must not
+    /// access the cache. It may fire while the strategy's initial keys are
+    /// preloaded, from within [`CacheBuilder::build`].
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `fraction` is in `(0, 1)`, since occupancy can't rise
+    /// above the capacity.
+    pub fn on_occupancy_above<F>(mut self, fraction: f64, callback: F) -> CacheBuilder<S, H>
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        assert!(
+            fraction > 0.0 && fraction < 1.0,
+            "occupancy watermark must be in (0, 1), got {fraction}"
+        );
+        self.occupancy_watermarks
+            .push((fraction, Box::new(callback)));
+        self
+    }
+}
+
+impl<S, H> CacheBuilder<S, H>
+where
+    S: CacheStrategy,
+    S::Err: From<Overloaded>,
+{
+    /// Sheds loads once `max_pending` of them are already pending (i.e.,
+    /// running or waiting for the strategy): instead of queuing, operations
+    /// which miss fail right away with [`Overloaded::TooManyPending`],
+    /// converted into the strategy's error and classified as
+    /// [`ErrorKind::Overloaded`]. Resident keys are served regardless.
+    /// Unlimited by default.
+    ///
+    /// [`ErrorKind::Overloaded`]: crate::ErrorKind::Overloaded
+    pub fn max_pending_loads(mut self, max_pending: usize) -> CacheBuilder<S, H> {
+        self.shed_policy().max_pending = max_pending;
+        self
+    }
+
+    /// Sheds loads which waited for the strategy (i.e., for other loads) for
+    /// longer than `max_wait`, failing them with [`Overloaded::WaitedTooLong`]
+    /// as in [`CacheBuilder::max_pending_loads`]. Unlimited by default.
+    pub fn max_load_wait(mut self, max_wait: Duration) -> CacheBuilder<S, H> {
+        self.shed_policy().max_wait = Some(max_wait);
+        self
+    }
+
+    fn shed_policy(&mut self) -> &mut ShedPolicy<S::Err> {
+        self.shedding.get_or_insert(ShedPolicy {
+            max_pending: usize::MAX,
+            max_wait: None,
+            to_err: <S::Err as From<Overloaded>>::from,
+        })
+    }
+}
+
+impl<S, H> CacheBuilder<S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Builds the cache.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is zero.
+    pub fn build(self) -> Cache<S, H> {
+        let capacity = self.capacity;
+        let mut cache = Cache::unwarmed(self.strategy, capacity, self.hasher);
+        for (fraction, callback) in self.occupancy_watermarks {
+            let above = (fraction * capacity as f64) as usize;
+            cache.occupancy.add_watermark(above, callback);
+        }
+        cache.load_queue = LoadQueue::new(self.shedding);
+        cache.batch_config = BatchConfig {
+            duplicates: self.duplicate_writes,
+            flush_mode: self.flush_mode,
+        };
+        if let Some(every) = self.trace_sampling {
+            cache.set_trace_sampling(every);
+        }
+        cache.set_event_journal_capacity(self.event_journal_capacity);
+        cache.set_load_budget(self.load_budget);
+        if self.warm_on_build {
+            cache.warm_initial_keys();
+        }
+        cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        misuse,
+        test_utils::{TestHashBuilder, TestStrategy, Tweaked},
+        MisusePolicy,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let c = CacheBuilder::new(TestStrategy::default(), 4)
+            .hasher(TestHashBuilder)
+            .build();
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&5).unwrap());
+        assert_eq!(c.slot_for_hash(5), 1);
+        assert_eq!(c.clone_strategy().count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "cache capacity must be non-zero")]
+    fn test_builder_zero_capacity() {
+        CacheBuilder::new(TestStrategy::default(), 0).build();
+    }
+
+    #[test]
+    fn test_builder_knobs() {
+        let c = CacheBuilder::new(Tweaked::preloading(&[1, 2]), 4)
+            .hasher(TestHashBuilder)
+            .event_journal_capacity(8)
+            .build();
+        assert_eq!(c.occupied(), 2);
+        assert_eq!(c.recent_events().len(), 2);
+
+        let c = CacheBuilder::new(Tweaked::preloading(&[1, 2]), 4)
+            .hasher(TestHashBuilder)
+            .warm_on_build(false)
+            .trace_sampling(0)
+            .load_budget(Duration::from_millis(1))
+            .flush_mode(FlushMode::ModifiedOnly)
+            .build();
+        assert_eq!(c.occupied(), 0);
+        assert!(c.recent_events().is_empty());
+
+        let mut wb = c.write_batch();
+        wb.write(&1, |_| ()).unwrap();
+        wb.write(&2, |val| val.push('!')).unwrap();
+        let mut flushed = Vec::new();
+        wb.flush(|val| {
+            flushed.push(val.clone());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(flushed, ["2two!"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "written twice in the same batch")]
+    fn test_builder_duplicate_writes() {
+        let c = CacheBuilder::new(TestStrategy::default(), 4)
+            .hasher(TestHashBuilder)
+            .duplicate_writes(DuplicateWrites::Misuse)
+            .build();
+        misuse::with_policy(MisusePolicy::Panic, || {
+            let mut wb = c.write_batch();
+            wb.write(&1, |_| ()).unwrap();
+            let _ = wb.write(&1, |_| ());
+        });
+    }
+}
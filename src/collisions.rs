@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+/// How many distinct colliding pairs are tracked. Pairs first seen once the
+/// limit is reached are only counted in the totals.
+const MAX_TRACKED_PAIRS: usize = 256;
+
+/// The cost of slot collisions, as opposed to cold misses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionStats {
+    /// How many resident keys were replaced by a load of another key mapping
+    /// to the same slot. Replacing remembered absences, or values predating
+    /// [`Cache::bump_epoch`], doesn't count.
+    ///
+    /// [`Cache::bump_epoch`]: crate::Cache::bump_epoch
+    pub keys_evicted_by_collision: u64,
+    /// How many loads were of the key last evicted from the slot by a
+    /// collision, i.e., wouldn't have been needed without the collision.
+    pub collision_reloads: u64,
+    /// The most frequent `(evicted, loaded)` key hash pairs, along with how
+    /// many evictions each accounts for, most frequent first.
+    pub top_pairs: Vec<((u64, u64), u64)>,
+}
+
+/// Tracks slot collisions, which are only recorded on loads.
+pub(crate) struct CollisionTracker {
+    evicted: AtomicU64,
+    reloads: AtomicU64,
+    last_evicted: Box<[LastEvicted]>,
+    /// Only locked on evictions, never on loads into empty or stale slots.
+    pairs: Mutex<HashMap<(u64, u64), u64>>,
+}
+
+/// The hash of the key last evicted from a slot by a collision, if `set`.
+/// Since loads record it while the slot is locked for write, it needn't be
+/// updated atomically as a whole.
+#[derive(Default)]
+struct LastEvicted {
+    set: AtomicBool,
+    hash: AtomicU64,
+}
+
+impl CollisionTracker {
+    pub(crate) fn new(capacity: usize) -> CollisionTracker {
+        CollisionTracker {
+            evicted: AtomicU64::new(0),
+            reloads: AtomicU64::new(0),
+            last_evicted: (0..capacity).map(|_| LastEvicted::default()).collect(),
+            pairs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records the load of the key with the given hash into the given slot,
+    /// which held a live, current value of the key with the `previous` hash,
+    /// if any.
+    pub(crate) fn record_load(&self, slot: usize, hash: u64, previous: Option<u64>) {
+        let last = &self.last_evicted[slot];
+        if last.set.load(Ordering::Relaxed) && last.hash.load(Ordering::Relaxed) == hash {
+            last.set.store(false, Ordering::Relaxed);
+            self.reloads.fetch_add(1, Ordering::Relaxed);
+        }
+        let Some(previous) = previous.filter(|&previous| previous != hash) else {
+            return;
+        };
+        self.evicted.fetch_add(1, Ordering::Relaxed);
+        last.hash.store(previous, Ordering::Relaxed);
+        last.set.store(true, Ordering::Relaxed);
+        let mut pairs = self.pairs();
+        let tracked = pairs.len();
+        if let Some(count) = pairs.get_mut(&(previous, hash)) {
+            *count += 1;
+        } else if tracked < MAX_TRACKED_PAIRS {
+            pairs.insert((previous, hash), 1);
+        }
+    }
+
+    /// Returns the current stats, with up to `top` colliding pairs.
+    pub(crate) fn stats(&self, top: usize) -> CollisionStats {
+        let mut top_pairs: Vec<_> = self
+            .pairs()
+            .iter()
+            .map(|(&pair, &count)| (pair, count))
+            .collect();
+        top_pairs.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        top_pairs.truncate(top);
+        CollisionStats {
+            keys_evicted_by_collision: self.evicted.load(Ordering::Relaxed),
+            collision_reloads: self.reloads.load(Ordering::Relaxed),
+            top_pairs,
+        }
+    }
+
+    fn pairs(&self) -> MutexGuard<'_, HashMap<(u64, u64), u64>> {
+        // The pairs are always left coherent, so poisoning can be ignored.
+        self.pairs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    #[test]
+    fn test_collision_stats() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        drop(c.read(&5).unwrap());
+        drop(c.read(&1).unwrap());
+        drop(c.read(&5).unwrap());
+
+        let stats = c.collision_stats(1);
+        assert_eq!(stats.keys_evicted_by_collision, 3);
+        assert_eq!(stats.collision_reloads, 2);
+        assert_eq!(stats.top_pairs, [((1, 5), 2)]);
+
+        // Stale values are replaced, not evicted.
+        c.bump_epoch();
+        drop(c.read(&1).unwrap());
+        let stats = c.collision_stats(1);
+        assert_eq!(stats.keys_evicted_by_collision, 3);
+        assert_eq!(stats.top_pairs, [((1, 5), 2)]);
+    }
+}
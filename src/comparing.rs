@@ -0,0 +1,204 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{Cache, CacheStrategy, LoadError, ReadRef, WriteRef};
+
+/// A harness mirroring every operation into two differently configured
+/// caches over the same keys and values, e.g. to evaluate a change of
+/// capacity, hasher or strategy against production traffic before switching.
+///
+/// The authoritative cache serves the operations; the shadow one is only
+/// compared against it, and its failures are counted rather than returned.
+/// Both caches load keys independently, so each load reaches both strategies.
+pub struct ComparingCache<A, B, HA = RandomState, HB = RandomState>
+where
+    A: CacheStrategy,
+    B: CacheStrategy,
+{
+    authoritative: Cache<A, HA>,
+    shadow: Cache<B, HB>,
+    report: Mutex<ComparisonReport>,
+}
+
+/// The divergence between the caches of a [`ComparingCache`], as gathered
+/// since it was constructed (or since [`ComparingCache::reset_report`]).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComparisonReport {
+    /// How many reads were mirrored.
+    pub reads: u64,
+    /// How many of them found the key resident in the authoritative cache.
+    pub authoritative_hits: u64,
+    /// How many of them found the key resident in the shadow cache.
+    pub shadow_hits: u64,
+    /// How many of them returned different values from each cache.
+    pub mismatches: u64,
+    /// How many mirrored operations failed on the shadow cache only.
+    pub shadow_errors: u64,
+}
+
+impl ComparisonReport {
+    /// Returns the hit rate of the authoritative cache, or zero if nothing was
+    /// read.
+    pub fn authoritative_hit_rate(&self) -> f64 {
+        rate(self.authoritative_hits, self.reads)
+    }
+
+    /// Returns the hit rate of the shadow cache, or zero if nothing was read.
+    pub fn shadow_hit_rate(&self) -> f64 {
+        rate(self.shadow_hits, self.reads)
+    }
+}
+
+fn rate(hits: u64, reads: u64) -> f64 {
+    if reads == 0 {
+        0.0
+    } else {
+        hits as f64 / reads as f64
+    }
+}
+
+impl<A, B, HA, HB> ComparingCache<A, B, HA, HB>
+where
+    A: CacheStrategy,
+    B: CacheStrategy<Key = A::Key, Val = A::Val>,
+    A::Key: Hash,
+    A::Val: PartialEq,
+    HA: BuildHasher,
+    HB: BuildHasher,
+{
+    /// Constructs a new harness over the given caches.
+    pub fn new(authoritative: Cache<A, HA>, shadow: Cache<B, HB>) -> ComparingCache<A, B, HA, HB> {
+        ComparingCache {
+            authoritative,
+            shadow,
+            report: Mutex::new(ComparisonReport::default()),
+        }
+    }
+
+    /// Acquires the value by the given key from the authoritative cache, for
+    /// read, comparing it with the shadow cache's.
+    ///
+    /// Whether the key was resident is checked right before reading it, so a
+    /// concurrent operation on the key may be counted as a hit or a miss.
+    pub fn read(&self, key: &A::Key) -> Result<ReadRef<'_, A::Val>, LoadError<A::Err>> {
+        let authoritative_hit = self.authoritative.contains(key);
+        let shadow_hit = self.shadow.contains(key);
+        let val = self.authoritative.read(key)?;
+        let shadow = self.shadow.read(key);
+
+        let mut report = self.lock_report();
+        report.reads += 1;
+        report.authoritative_hits += u64::from(authoritative_hit);
+        report.shadow_hits += u64::from(shadow_hit);
+        match shadow {
+            Ok(shadow) => report.mismatches += u64::from(*shadow != *val),
+            Err(_) => report.shadow_errors += 1,
+        }
+        drop(report);
+        Ok(val)
+    }
+
+    /// Modifies the value by the given key with the given function in both
+    /// caches, returning the authoritative cache's guard over it.
+    pub fn write<F>(&self, key: &A::Key, f: F) -> Result<WriteRef<'_, A::Val>, LoadError<A::Err>>
+    where
+        F: Fn(&mut A::Val),
+    {
+        let mut val = self.authoritative.write(key)?;
+        f(&mut val);
+        match self.shadow.write(key) {
+            Ok(mut shadow) => f(&mut shadow),
+            Err(_) => self.lock_report().shadow_errors += 1,
+        }
+        Ok(val)
+    }
+
+    /// Places the given value in both caches, as in [`Cache::insert`],
+    /// returning the value it displaced from the authoritative cache.
+    pub fn insert(&self, key: &A::Key, val: A::Val) -> Option<A::Val>
+    where
+        A::Val: Clone,
+    {
+        self.shadow.insert(key, val.clone());
+        self.authoritative.insert(key, val)
+    }
+
+    /// Removes the value by the given key from both caches, as in
+    /// [`Cache::invalidate`], returning the authoritative cache's.
+    pub fn invalidate(&self, key: &A::Key) -> Option<A::Val> {
+        self.shadow.invalidate(key);
+        self.authoritative.invalidate(key)
+    }
+
+    /// Returns the divergence gathered so far.
+    pub fn report(&self) -> ComparisonReport {
+        self.lock_report().clone()
+    }
+
+    /// Clears the divergence gathered so far.
+    pub fn reset_report(&self) {
+        *self.lock_report() = ComparisonReport::default();
+    }
+
+    /// Returns the authoritative cache.
+    pub fn authoritative(&self) -> &Cache<A, HA> {
+        &self.authoritative
+    }
+
+    /// Returns the shadow cache.
+    pub fn shadow(&self) -> &Cache<B, HB> {
+        &self.shadow
+    }
+
+    /// Returns the caches, authoritative first.
+    pub fn into_caches(self) -> (Cache<A, HA>, Cache<B, HB>) {
+        (self.authoritative, self.shadow)
+    }
+
+    fn lock_report(&self) -> MutexGuard<'_, ComparisonReport> {
+        // The report is always left coherent, so poisoning can be ignored.
+        self.report.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestHashBuilder, TestStrategy};
+
+    use super::*;
+
+    #[test]
+    fn test_comparing_cache() {
+        let c = ComparingCache::new(
+            Cache::<TestStrategy, TestHashBuilder>::new::<8>(TestStrategy::default()),
+            Cache::<TestStrategy, TestHashBuilder>::new::<4>(TestStrategy::default()),
+        );
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&5).unwrap());
+        // `5` displaced `1` from the smaller shadow cache only.
+        drop(c.read(&1).unwrap());
+        drop(c.write(&1, |val| val.push_str("-mod")).unwrap());
+        c.shadow().write(&1).unwrap().push_str("-drift");
+        drop(c.read(&1).unwrap());
+
+        let report = c.report();
+        assert_eq!(
+            report,
+            ComparisonReport {
+                reads: 4,
+                authoritative_hits: 2,
+                shadow_hits: 1,
+                mismatches: 1,
+                shadow_errors: 0,
+            }
+        );
+        assert_eq!(report.authoritative_hit_rate(), 0.5);
+        assert_eq!(report.shadow_hit_rate(), 0.25);
+        assert_eq!(c.invalidate(&1).as_deref(), Some("1one-mod"));
+        assert!(!c.shadow().contains(&1));
+    }
+}
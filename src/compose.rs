@@ -0,0 +1,301 @@
+use std::{convert::Infallible, marker::PhantomData};
+
+use crate::{CacheStrategy, ErrorKind, Validation};
+
+/// The loading half of a [`CacheStrategy`].
+pub trait Loader {
+    type Key;
+    type Val;
+    type Err;
+
+    /// Loads the value for the given key.
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err>;
+
+    /// Classifies the given load error, as in [`CacheStrategy::classify_err`].
+    fn classify_err(err: &Self::Err) -> ErrorKind {
+        let _ = err;
+        ErrorKind::Transient
+    }
+
+    /// Returns the keys to be loaded eagerly when the cache is constructed, as
+    /// in [`CacheStrategy::initial_keys`].
+    fn initial_keys(&self) -> Vec<Self::Key> {
+        Vec::new()
+    }
+
+    /// Tells whether the given values are identical, as in
+    /// [`CacheStrategy::same_val`].
+    fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+        let _ = (a, b);
+        false
+    }
+
+    /// Called after a stale value of the given key was reloaded into a
+    /// different one, as in [`CacheStrategy::on_divergence`].
+    fn on_divergence(&mut self, key: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+        let _ = (key, stale, fresh);
+    }
+
+    /// Checks domain invariants of the given value of the given key, as in
+    /// [`CacheStrategy::debug_validate`].
+    fn debug_validate(&self, key: &Self::Key, val: &Self::Val) {
+        let _ = (key, val);
+    }
+}
+
+/// The key validation half of a [`CacheStrategy`].
+///
+/// `E` is the error of the loader it is composed with, which fallible
+/// validation fails with. Validators which never fail should be implemented
+/// for every `E`, so that they compose with any loader.
+pub trait Validator<K, V, E = Infallible> {
+    /// Checks the given key against the given value, with the same meaning as
+    /// [`CacheStrategy::match_kv`].
+    fn match_kv(key: &K, val: &V) -> bool;
+
+    /// Checks the given value against the given key, as in
+    /// [`CacheStrategy::validate`].
+    fn validate(key: &K, val: &V) -> Validation {
+        if Self::match_kv(key, val) {
+            Validation::Conflict
+        } else {
+            Validation::Match
+        }
+    }
+
+    /// Like [`Validator::validate`], but may fail, as in
+    /// [`CacheStrategy::try_validate`].
+    fn try_validate(key: &K, val: &V) -> Result<Validation, E> {
+        Ok(Self::validate(key, val))
+    }
+}
+
+/// A strategy composed of a [`Loader`] and a [`Validator`], so that each may
+/// be swapped (or mocked) independently of the other.
+pub struct Composed<L, V> {
+    loader: L,
+    _validator: PhantomData<fn() -> V>,
+}
+
+impl<L, V> Composed<L, V> {
+    /// Constructs a new strategy over the given loader, validating keys with
+    /// `V`.
+    pub fn new(loader: L) -> Composed<L, V> {
+        Composed {
+            loader,
+            _validator: PhantomData,
+        }
+    }
+
+    /// Returns the loader.
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+
+    /// Returns the loader.
+    pub fn into_loader(self) -> L {
+        self.loader
+    }
+}
+
+impl<L, V> Clone for Composed<L, V>
+where
+    L: Clone,
+{
+    fn clone(&self) -> Self {
+        Composed::new(self.loader.clone())
+    }
+}
+
+impl<L, V> Default for Composed<L, V>
+where
+    L: Default,
+{
+    fn default() -> Self {
+        Composed::new(L::default())
+    }
+}
+
+impl<L, V> CacheStrategy for Composed<L, V>
+where
+    L: Loader,
+    V: Validator<L::Key, L::Val, L::Err>,
+{
+    type Key = L::Key;
+    type Val = L::Val;
+    type Err = L::Err;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        self.loader.load(key)
+    }
+
+    fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+        V::match_kv(key, val)
+    }
+
+    fn validate(key: &Self::Key, val: &Self::Val) -> Validation {
+        V::validate(key, val)
+    }
+
+    fn try_validate(key: &Self::Key, val: &Self::Val) -> Result<Validation, Self::Err> {
+        V::try_validate(key, val)
+    }
+
+    fn classify_err(err: &Self::Err) -> ErrorKind {
+        L::classify_err(err)
+    }
+
+    fn initial_keys(&self) -> Vec<Self::Key> {
+        self.loader.initial_keys()
+    }
+
+    fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+        L::same_val(a, b)
+    }
+
+    fn on_divergence(&mut self, key: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+        self.loader.on_divergence(key, stale, fresh);
+    }
+
+    fn debug_validate(&self, key: &Self::Key, val: &Self::Val) {
+        self.loader.debug_validate(key, val);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::{test_utils::TestHashBuilder, Cache, CacheBuilder};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Formatting {
+        count: u32,
+    }
+
+    impl Loader for Formatting {
+        type Key = u32;
+        type Val = String;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.count += 1;
+            Ok(format!("{key}!"))
+        }
+    }
+
+    struct ByPrefix;
+
+    impl<E> Validator<u32, String, E> for ByPrefix {
+        fn match_kv(key: &u32, val: &String) -> bool {
+            !val.starts_with(&format!("{key}!"))
+        }
+    }
+
+    #[test]
+    fn test_composed_strategy() {
+        let s = Composed::<Formatting, ByPrefix>::default();
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(&*c.read(&1).unwrap(), "1!");
+        assert_eq!(&*c.read(&1).unwrap(), "1!");
+        assert_eq!(&*c.read(&5).unwrap(), "5!");
+        assert_eq!(c.into_strategy().into_loader().count, 2);
+    }
+
+    #[test]
+    fn test_composed_builder() {
+        let c = CacheBuilder::<Composed<_, ByPrefix>, _>::composed(Formatting::default(), 4)
+            .hasher(TestHashBuilder)
+            .build();
+
+        assert_eq!(&*c.read(&1).unwrap(), "1!");
+        assert_eq!(&*c.read(&5).unwrap(), "5!");
+        assert_eq!(c.into_strategy().into_loader().count, 2);
+    }
+
+    #[test]
+    fn test_composed_validation() {
+        /// The version values are loaded at; `u32::MAX` fails validation.
+        static VERSION: AtomicU32 = AtomicU32::new(0);
+
+        #[derive(Default)]
+        struct Versioned {
+            reloads: u32,
+        }
+
+        impl Loader for Versioned {
+            type Key = u32;
+            type Val = (u32, u32);
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                Ok((*key, VERSION.load(Ordering::SeqCst)))
+            }
+
+            fn on_divergence(&mut self, _: &Self::Key, _: &Self::Val, _: &Self::Val) {
+                self.reloads += 1;
+            }
+        }
+
+        struct ByVersion;
+
+        impl Validator<u32, (u32, u32), ()> for ByVersion {
+            fn match_kv(key: &u32, val: &(u32, u32)) -> bool {
+                *key != val.0
+            }
+
+            fn validate(key: &u32, val: &(u32, u32)) -> Validation {
+                if Self::match_kv(key, val) {
+                    Validation::Conflict
+                } else if val.1 != VERSION.load(Ordering::SeqCst) {
+                    Validation::Stale
+                } else {
+                    Validation::Match
+                }
+            }
+
+            fn try_validate(key: &u32, val: &(u32, u32)) -> Result<Validation, ()> {
+                if VERSION.load(Ordering::SeqCst) == u32::MAX {
+                    return Err(());
+                }
+                Ok(Self::validate(key, val))
+            }
+        }
+
+        let s = Composed::<Versioned, ByVersion>::default();
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+        assert_eq!(*c.read(&1).unwrap(), (1, 0));
+        VERSION.store(1, Ordering::SeqCst);
+        assert_eq!(*c.read(&1).unwrap(), (1, 1));
+        VERSION.store(u32::MAX, Ordering::SeqCst);
+        assert!(c.read(&1).is_err());
+        assert_eq!(c.into_strategy().into_loader().reloads, 1);
+    }
+
+    #[test]
+    fn test_composed_initial_keys() {
+        struct Preloading(Formatting);
+
+        impl Loader for Preloading {
+            type Key = u32;
+            type Val = String;
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                self.0.load(key)
+            }
+
+            fn initial_keys(&self) -> Vec<Self::Key> {
+                vec![1, 2]
+            }
+        }
+
+        let s = Composed::<_, ByPrefix>::new(Preloading(Formatting::default()));
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+        assert!(c.contains(&1) && c.contains(&2));
+        assert_eq!(c.into_strategy().into_loader().0.count, 2);
+    }
+}
@@ -0,0 +1,200 @@
+use std::{marker::PhantomData, sync::OnceLock};
+
+use crate::{CacheStrategy, ErrorKind, Validation};
+
+/// A value which may be decoded from its raw (e.g., byte) representation.
+pub trait DecodeFrom<B>: Sized {
+    type Err;
+
+    /// Decodes the value from the given raw representation.
+    fn decode_from(raw: &B) -> Result<Self, Self::Err>;
+}
+
+/// A cache value kept in its raw form, plus a lazily decoded form which is
+/// memoized alongside it (i.e., under the same slot lock).
+pub struct Encoded<V, B = Vec<u8>> {
+    raw: B,
+    decoded: OnceLock<V>,
+}
+
+impl<V, B> Encoded<V, B>
+where
+    V: DecodeFrom<B>,
+{
+    /// Constructs a new value from its raw form.
+    pub fn new(raw: B) -> Encoded<V, B> {
+        Encoded {
+            raw,
+            decoded: OnceLock::new(),
+        }
+    }
+
+    /// Returns the raw form.
+    pub fn raw(&self) -> &B {
+        &self.raw
+    }
+
+    /// Returns the raw form for modification. This drops the decoded form,
+    /// which is recomputed on the next call to [`Encoded::decoded`].
+    pub fn raw_mut(&mut self) -> &mut B {
+        self.decoded.take();
+        &mut self.raw
+    }
+
+    /// Returns the decoded form, decoding it on first access.
+    ///
+    /// Decoding errors aren't memoized. Concurrent readers may end up decoding
+    /// the same raw value more than once, but they all observe the same
+    /// decoded instance.
+    pub fn decoded(&self) -> Result<&V, V::Err> {
+        if let Some(val) = self.decoded.get() {
+            return Ok(val);
+        }
+        let val = V::decode_from(&self.raw)?;
+        Ok(self.decoded.get_or_init(|| val))
+    }
+
+    /// Returns whether the decoded form has already been computed.
+    pub fn is_decoded(&self) -> bool {
+        self.decoded.get().is_some()
+    }
+
+    /// Returns the raw form, dropping the decoded one.
+    pub fn into_raw(self) -> B {
+        self.raw
+    }
+}
+
+/// A strategy which wraps another strategy loading raw values, so that the
+/// cache stores [`Encoded`] values decoded lazily to `V`.
+pub struct DecodeStrategy<S, V> {
+    inner: S,
+    _val: PhantomData<fn() -> V>,
+}
+
+impl<S, V> DecodeStrategy<S, V> {
+    /// Constructs a new decoding strategy over the given one.
+    pub fn new(inner: S) -> DecodeStrategy<S, V> {
+        DecodeStrategy {
+            inner,
+            _val: PhantomData,
+        }
+    }
+
+    /// Returns the inner strategy.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the inner strategy.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, V> Clone for DecodeStrategy<S, V>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        DecodeStrategy::new(self.inner.clone())
+    }
+}
+
+impl<S, V> CacheStrategy for DecodeStrategy<S, V>
+where
+    S: CacheStrategy,
+    V: DecodeFrom<S::Val>,
+{
+    type Key = S::Key;
+    type Val = Encoded<V, S::Val>;
+    type Err = S::Err;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        self.inner.load(key).map(Encoded::new)
+    }
+
+    fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+        S::match_kv(key, &val.raw)
+    }
+
+    fn validate(key: &Self::Key, val: &Self::Val) -> Validation {
+        S::validate(key, &val.raw)
+    }
+
+    fn try_validate(key: &Self::Key, val: &Self::Val) -> Result<Validation, Self::Err> {
+        S::try_validate(key, &val.raw)
+    }
+
+    fn classify_err(err: &Self::Err) -> ErrorKind {
+        S::classify_err(err)
+    }
+
+    fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+        S::same_val(&a.raw, &b.raw)
+    }
+
+    fn on_divergence(&mut self, key: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+        self.inner.on_divergence(key, &stale.raw, &fresh.raw);
+    }
+
+    fn debug_validate(&self, key: &Self::Key, val: &Self::Val) {
+        self.inner.debug_validate(key, &val.raw);
+    }
+
+    fn initial_keys(&self) -> Vec<Self::Key> {
+        self.inner.initial_keys()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    static DECODES: AtomicU32 = AtomicU32::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Len(usize);
+
+    impl DecodeFrom<String> for Len {
+        type Err = ();
+
+        fn decode_from(raw: &String) -> Result<Self, Self::Err> {
+            DECODES.fetch_add(1, Ordering::SeqCst);
+            Ok(Len(raw.len()))
+        }
+    }
+
+    #[test]
+    fn test_decode_memoized() {
+        let s = DecodeStrategy::<_, Len>::new(TestStrategy::default());
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+
+        {
+            let val = c.read(&1).unwrap();
+            assert!(!val.is_decoded());
+            assert_eq!(val.raw(), "1one");
+            assert_eq!(val.decoded(), Ok(&Len(4)));
+        }
+        {
+            let val = c.read(&1).unwrap();
+            assert_eq!(val.decoded(), Ok(&Len(4)));
+            assert_eq!(DECODES.load(Ordering::SeqCst), 1);
+        }
+        {
+            let mut val = c.write(&1).unwrap();
+            val.raw_mut().push_str("-mod");
+            assert!(!val.is_decoded());
+            assert_eq!(val.decoded(), Ok(&Len(8)));
+            assert_eq!(DECODES.load(Ordering::SeqCst), 2);
+        }
+        assert_eq!(c.clone_strategy().inner().count(), 1);
+    }
+}
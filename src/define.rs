@@ -0,0 +1,130 @@
+/// Defines a newtype over a [`Cache`] of the given strategy (and, optionally,
+/// hasher), whose accessors have domain-specific names, so that the cache's
+/// generics stay out of application signatures.
+///
+/// ```
+/// # use granular_cache::{define_cache, CacheStrategy};
+/// # struct PageStrategy;
+/// # impl CacheStrategy for PageStrategy {
+/// #     type Key = u64;
+/// #     type Val = (u64, Vec<u8>);
+/// #     type Err = std::io::Error;
+/// #     fn load(&mut self, id: &u64) -> Result<Self::Val, Self::Err> {
+/// #         Ok((*id, Vec::new()))
+/// #     }
+/// #     fn match_kv(id: &u64, page: &Self::Val) -> bool {
+/// #         page.0 != *id
+/// #     }
+/// # }
+/// define_cache! {
+///     /// The cache of pages, by ID.
+///     pub struct PageCache(PageStrategy);
+///     read = get_page;
+///     write = get_page_mut;
+///     batch = write_pages;
+/// }
+///
+/// let pages = PageCache::new(PageStrategy, 64);
+/// assert_eq!(pages.get_page(&7).unwrap().0, 7);
+/// ```
+///
+/// Each of the `read`, `write` and `batch` accessors is optional. The
+/// underlying cache is always reachable through `inner`.
+///
+/// [`Cache`]: crate::Cache
+#[macro_export]
+macro_rules! define_cache {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($strategy:ty);
+        $($accessor:ident = $method:ident;)*
+    ) => {
+        $crate::define_cache! {
+            $(#[$meta])*
+            $vis struct $name($strategy, ::std::collections::hash_map::RandomState);
+            $($accessor = $method;)*
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($strategy:ty, $hasher:ty);
+        $($accessor:ident = $method:ident;)*
+    ) => {
+        $(#[$meta])*
+        $vis struct $name($crate::Cache<$strategy, $hasher>);
+
+        impl $name {
+            /// Constructs a new cache with the given number of slots.
+            #[allow(dead_code)]
+            $vis fn new(strategy: $strategy, capacity: usize) -> Self {
+                $name($crate::Cache::with_capacity(strategy, capacity))
+            }
+
+            /// Returns the underlying cache.
+            #[allow(dead_code)]
+            $vis fn inner(&self) -> &$crate::Cache<$strategy, $hasher> {
+                &self.0
+            }
+
+            $($crate::define_cache!(@accessor $vis $accessor $method $strategy, $hasher);)*
+        }
+    };
+    (@accessor $vis:vis read $method:ident $strategy:ty, $hasher:ty) => {
+        /// Acquires the value by the given key, for read.
+        $vis fn $method(
+            &self,
+            key: &<$strategy as $crate::CacheStrategy>::Key,
+        ) -> ::std::result::Result<
+            $crate::ReadRef<'_, <$strategy as $crate::CacheStrategy>::Val>,
+            $crate::LoadError<<$strategy as $crate::CacheStrategy>::Err>,
+        > {
+            self.0.read(key)
+        }
+    };
+    (@accessor $vis:vis write $method:ident $strategy:ty, $hasher:ty) => {
+        /// Acquires the value by the given key, for write.
+        $vis fn $method(
+            &self,
+            key: &<$strategy as $crate::CacheStrategy>::Key,
+        ) -> ::std::result::Result<
+            $crate::WriteRef<'_, <$strategy as $crate::CacheStrategy>::Val>,
+            $crate::LoadError<<$strategy as $crate::CacheStrategy>::Err>,
+        > {
+            self.0.write(key)
+        }
+    };
+    (@accessor $vis:vis batch $method:ident $strategy:ty, $hasher:ty) => {
+        /// Starts a write batch.
+        $vis fn $method(&self) -> $crate::WriteBatch<'_, $strategy, $hasher> {
+            self.0.write_batch()
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestHashBuilder, TestStrategy};
+
+    define_cache! {
+        struct NameCache(TestStrategy, TestHashBuilder);
+        read = get_name;
+        write = get_name_mut;
+        batch = rename;
+    }
+
+    #[test]
+    fn test_define_cache() {
+        let names = NameCache::new(TestStrategy::default(), 4);
+
+        assert_eq!(&*names.get_name(&1).unwrap(), "1one");
+        names.get_name_mut(&1).unwrap().push_str("-mod");
+
+        let mut batch = names.rename();
+        batch.write(&2, |val| val.push_str("-mod")).unwrap();
+        batch.flush_all(|_| Ok::<_, ()>(())).unwrap();
+
+        assert_eq!(&*names.get_name(&1).unwrap(), "1one-mod");
+        assert_eq!(&*names.get_name(&2).unwrap(), "2two-mod");
+        assert_eq!(names.inner().clone_strategy().count(), 2);
+    }
+}
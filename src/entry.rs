@@ -0,0 +1,270 @@
+use std::{
+    hash::{BuildHasher, Hash},
+    sync::RwLockWriteGuard,
+};
+
+use crate::{
+    guards::GuardToken, Cache, CacheStrategy, Entry, EventOutcome, GuardKind, LoadError, Operation,
+    WriteRef,
+};
+
+/// A view into the slot of a key, locked for write, as obtained from
+/// [`Cache::entry`].
+pub enum CacheEntry<'a, S, H>
+where
+    S: CacheStrategy,
+{
+    /// The slot holds the key's value.
+    Occupied(OccupiedEntry<'a, S, H>),
+    /// The slot holds no value.
+    Vacant(VacantEntry<'a, S, H>),
+    /// The slot holds a value which conflicts with the key, e.g. that of
+    /// another key mapping to the same slot.
+    Conflicted(ConflictedEntry<'a, S, H>),
+}
+
+/// The slot of a key, locked for write.
+struct Locked<'a, S, H>
+where
+    S: CacheStrategy,
+{
+    cache: &'a Cache<S, H>,
+    key: &'a S::Key,
+    hash: u64,
+    guard: RwLockWriteGuard<'a, Option<Entry<S::Val>>>,
+    token: GuardToken<'a>,
+}
+
+impl<'a, S, H> Locked<'a, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    fn insert(mut self, val: S::Val) -> WriteRef<'a, S::Val> {
+        self.cache
+            .store_val(self.key, self.hash, Operation::Insert, &mut self.guard, val);
+        WriteRef::new(self.guard, self.token)
+    }
+
+    fn load(self) -> Result<WriteRef<'a, S::Val>, LoadError<S::Err>> {
+        self.load_entry().map(OccupiedEntry::into_write_ref)
+    }
+
+    fn load_entry(mut self) -> Result<OccupiedEntry<'a, S, H>, LoadError<S::Err>> {
+        self.cache
+            .load(self.key, self.hash, Operation::Write, &mut self.guard)?;
+        Ok(OccupiedEntry(self))
+    }
+}
+
+impl<'a, S, H> CacheEntry<'a, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Locks the slot of the given key for write.
+    pub(crate) fn new(cache: &'a Cache<S, H>, key: &'a S::Key) -> CacheEntry<'a, S, H> {
+        let hash = cache.hash(key);
+        let slot = cache.slot_for_hash(hash);
+        let guard = cache.slot_at(slot).write();
+        let occupied = !cache.is_miss(key, &guard);
+        let conflicted = guard
+            .as_ref()
+            .is_some_and(|entry| entry.val.is_some() && cache.is_current(entry));
+        let locked = Locked {
+            cache,
+            key,
+            hash,
+            guard,
+            token: cache.guards.track(slot, GuardKind::Write),
+        };
+        if occupied {
+            cache.record(Operation::Write, hash, slot, EventOutcome::Hit);
+            CacheEntry::Occupied(OccupiedEntry(locked))
+        } else if conflicted {
+            CacheEntry::Conflicted(ConflictedEntry(locked))
+        } else {
+            CacheEntry::Vacant(VacantEntry(locked))
+        }
+    }
+
+    /// Returns the key's value, loading it with the strategy unless the slot
+    /// holds it.
+    pub fn or_load(self) -> Result<WriteRef<'a, S::Val>, LoadError<S::Err>> {
+        match self {
+            CacheEntry::Occupied(entry) => Ok(entry.into_write_ref()),
+            CacheEntry::Vacant(entry) => entry.load(),
+            CacheEntry::Conflicted(entry) => entry.load(),
+        }
+    }
+
+    /// Like [`CacheEntry::or_load`], but keeps the slot as an occupied entry,
+    /// e.g. so that the value may still be removed under the same lock.
+    pub fn or_load_entry(self) -> Result<OccupiedEntry<'a, S, H>, LoadError<S::Err>> {
+        match self {
+            CacheEntry::Occupied(entry) => Ok(entry),
+            CacheEntry::Vacant(entry) => entry.0.load_entry(),
+            CacheEntry::Conflicted(entry) => entry.0.load_entry(),
+        }
+    }
+
+    /// Returns the key's value, computing it with the given function unless
+    /// the slot holds it.
+    pub fn or_insert_with<F>(self, f: F) -> WriteRef<'a, S::Val>
+    where
+        F: FnOnce() -> S::Val,
+    {
+        match self {
+            CacheEntry::Occupied(entry) => entry.into_write_ref(),
+            CacheEntry::Vacant(entry) => entry.insert(f()),
+            CacheEntry::Conflicted(entry) => entry.insert(f()),
+        }
+    }
+}
+
+/// A slot holding the value of its key.
+pub struct OccupiedEntry<'a, S, H>(Locked<'a, S, H>)
+where
+    S: CacheStrategy;
+
+impl<'a, S, H> OccupiedEntry<'a, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Returns the value.
+    pub fn get(&self) -> &S::Val {
+        Entry::val(&self.0.guard)
+    }
+
+    /// Returns the value, for modification.
+    pub fn get_mut(&mut self) -> &mut S::Val {
+        Entry::val_mut(&mut self.0.guard)
+    }
+
+    /// Replaces the value, returning the previous one.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn insert(&mut self, val: S::Val) -> S::Val {
+        let locked = &mut self.0;
+        let displaced = locked.cache.store_val(
+            locked.key,
+            locked.hash,
+            Operation::Insert,
+            &mut locked.guard,
+            val,
+        );
+        displaced.expect("occupied slot held a value")
+    }
+
+    /// Removes the value from the slot, returning it.
+    pub fn remove(mut self) -> S::Val {
+        let displaced = self.0.cache.replace_entry(&mut self.0.guard, None);
+        displaced
+            .and_then(|entry| entry.val)
+            .expect("occupied slot held a value")
+    }
+
+    /// Converts the entry into a guard over its value.
+    pub fn into_write_ref(self) -> WriteRef<'a, S::Val> {
+        WriteRef::new(self.0.guard, self.0.token)
+    }
+}
+
+/// A slot holding no value.
+pub struct VacantEntry<'a, S, H>(Locked<'a, S, H>)
+where
+    S: CacheStrategy;
+
+impl<'a, S, H> VacantEntry<'a, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Places the given value in the slot, without loading it.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn insert(self, val: S::Val) -> WriteRef<'a, S::Val> {
+        self.0.insert(val)
+    }
+
+    /// Loads the key's value into the slot with the strategy.
+    pub fn load(self) -> Result<WriteRef<'a, S::Val>, LoadError<S::Err>> {
+        self.0.load()
+    }
+}
+
+/// A slot holding a value which conflicts with the key.
+pub struct ConflictedEntry<'a, S, H>(Locked<'a, S, H>)
+where
+    S: CacheStrategy;
+
+impl<'a, S, H> ConflictedEntry<'a, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Returns the conflicting value.
+    pub fn conflicting(&self) -> &S::Val {
+        Entry::val(&self.0.guard)
+    }
+
+    /// Replaces the conflicting value by the given one, without loading it.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn insert(self, val: S::Val) -> WriteRef<'a, S::Val> {
+        self.0.insert(val)
+    }
+
+    /// Replaces the conflicting value by the key's, loaded with the strategy.
+    pub fn load(self) -> Result<WriteRef<'a, S::Val>, LoadError<S::Err>> {
+        self.0.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache, CacheEntry,
+    };
+
+    #[test]
+    fn test_entry() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let CacheEntry::Vacant(entry) = c.entry(&1) else {
+            panic!("expected a vacant entry");
+        };
+        entry.load().unwrap().push_str("-mod");
+
+        let CacheEntry::Conflicted(entry) = c.entry(&5) else {
+            panic!("expected a conflicted entry");
+        };
+        assert_eq!(entry.conflicting(), "1one-mod");
+        drop(entry.insert("5inline".to_string()));
+
+        let CacheEntry::Occupied(mut entry) = c.entry(&5) else {
+            panic!("expected an occupied entry");
+        };
+        assert_eq!(entry.insert("5five".to_string()), "5inline");
+        assert_eq!(entry.remove(), "5five");
+
+        assert_eq!(&*c.entry(&1).or_insert_with(|| "1new".to_string()), "1new");
+        assert_eq!(&*c.entry(&1).or_load().unwrap(), "1new");
+        assert_eq!(c.clone_strategy().count(), 1);
+        assert_eq!(c.entry(&5).or_load_entry().unwrap().remove(), "5five");
+        assert!(!c.contains(&1));
+        assert_eq!(c.clone_strategy().count(), 2);
+        c.check_invariants();
+    }
+}
@@ -0,0 +1,331 @@
+use std::{error::Error, fmt, time::Duration};
+
+use crate::{BatchWriteError, HandleError, MultiKeyError, TimeoutError};
+
+/// The cache operation during which a load was attempted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    Read,
+    Write,
+    Batch,
+    Preload,
+    Validate,
+    Insert,
+    ExternalLoad,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operation::Read => "read",
+            Operation::Write => "write",
+            Operation::Batch => "batch write",
+            Operation::Preload => "preload",
+            Operation::Validate => "strategy validation",
+            Operation::Insert => "insert",
+            Operation::ExternalLoad => "external load",
+        })
+    }
+}
+
+/// How a strategy error should be treated, as classified by
+/// [`CacheStrategy::classify_err`].
+///
+/// [`CacheStrategy::classify_err`]: crate::CacheStrategy::classify_err
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The load may succeed if retried.
+    Transient,
+    /// The load will keep failing for this key.
+    Permanent,
+    /// The key doesn't exist in the backing store.
+    NotFound,
+    /// The load was shed by the cache, without reaching the strategy (see
+    /// [`Overloaded`]).
+    ///
+    /// [`Overloaded`]: crate::Overloaded
+    Overloaded,
+}
+
+/// A strategy load error, along with the context in which the load failed.
+///
+/// The error is `Copy` whenever the strategy's error is, so that it may be
+/// propagated (e.g. across FFI boundaries) without allocating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadError<E> {
+    err: E,
+    kind: ErrorKind,
+    key_hash: u64,
+    slot: usize,
+    operation: Operation,
+}
+
+impl<E> LoadError<E> {
+    pub(crate) fn new(
+        err: E,
+        kind: ErrorKind,
+        key_hash: u64,
+        slot: usize,
+        operation: Operation,
+    ) -> LoadError<E> {
+        LoadError {
+            err,
+            kind,
+            key_hash,
+            slot,
+            operation,
+        }
+    }
+
+    /// Returns the error returned by the strategy.
+    pub fn err(&self) -> &E {
+        &self.err
+    }
+
+    /// Returns the error returned by the strategy.
+    pub fn into_err(self) -> E {
+        self.err
+    }
+
+    /// Returns how the strategy classified the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the hash of the key whose load failed, as computed by the
+    /// cache's hasher.
+    pub fn key_hash(&self) -> u64 {
+        self.key_hash
+    }
+
+    /// Returns the index of the slot the key maps to.
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    /// Returns the operation which triggered the load.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+}
+
+impl<E> fmt::Display for LoadError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to load key with hash {:#018x} into slot {} on {}: {}",
+            self.key_hash, self.slot, self.operation, self.err
+        )
+    }
+}
+
+impl<E> Error for LoadError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.err)
+    }
+}
+
+/// Any error of the cache's operations, for callers which mix them and want
+/// to propagate their errors as a single type.
+///
+/// Each operation returns the narrowest error it can fail with, all of which
+/// convert into this one. Like them, it is `Copy` whenever the strategy's
+/// error is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheError<E> {
+    /// The strategy failed to load the key.
+    Load(LoadError<E>),
+    /// The key's slot stayed locked (see [`TimeoutError::TimedOut`]).
+    TimedOut { waited: Duration },
+    /// Two of the keys acquired at once map to the same slot (see
+    /// [`MultiKeyError::SameSlot`]).
+    SameSlot {
+        slot: usize,
+        first: usize,
+        second: usize,
+    },
+    /// A recursive load read a key whose slot is being loaded further up the
+    /// chain (see [`HandleError::SameSlot`]).
+    LoadingSlot { slot: usize },
+    /// A recursive load read a key whose slot is locked (see
+    /// [`HandleError::WouldBlock`]).
+    WouldBlock { slot: usize },
+    /// A key was written twice in the same batch (see
+    /// [`BatchWriteError::DuplicateKey`]).
+    DuplicateKey { key_hash: u64 },
+}
+
+impl<E> CacheError<E> {
+    /// Returns the load error, if the strategy failed.
+    pub fn load_error(&self) -> Option<&LoadError<E>> {
+        match self {
+            CacheError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<LoadError<E>> for CacheError<E> {
+    fn from(err: LoadError<E>) -> Self {
+        CacheError::Load(err)
+    }
+}
+
+impl<E> From<TimeoutError<E>> for CacheError<E> {
+    fn from(err: TimeoutError<E>) -> Self {
+        match err {
+            TimeoutError::TimedOut { waited } => CacheError::TimedOut { waited },
+            TimeoutError::Load(err) => CacheError::Load(err),
+        }
+    }
+}
+
+impl<E> From<MultiKeyError<E>> for CacheError<E> {
+    fn from(err: MultiKeyError<E>) -> Self {
+        match err {
+            MultiKeyError::SameSlot {
+                slot,
+                first,
+                second,
+            } => CacheError::SameSlot {
+                slot,
+                first,
+                second,
+            },
+            MultiKeyError::Load(err) => CacheError::Load(err),
+        }
+    }
+}
+
+impl<E> From<HandleError<E>> for CacheError<E> {
+    fn from(err: HandleError<E>) -> Self {
+        match err {
+            HandleError::SameSlot { slot } => CacheError::LoadingSlot { slot },
+            HandleError::WouldBlock { slot } => CacheError::WouldBlock { slot },
+            HandleError::Load(err) => CacheError::Load(err),
+        }
+    }
+}
+
+impl<E> From<BatchWriteError<E>> for CacheError<E> {
+    fn from(err: BatchWriteError<E>) -> Self {
+        match err {
+            BatchWriteError::DuplicateKey { key_hash } => CacheError::DuplicateKey { key_hash },
+            BatchWriteError::Load(err) => CacheError::Load(err),
+        }
+    }
+}
+
+impl<E> fmt::Display for CacheError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Load(err) => err.fmt(f),
+            CacheError::TimedOut { waited } => {
+                TimeoutError::<E>::TimedOut { waited: *waited }.fmt(f)
+            }
+            CacheError::SameSlot {
+                slot,
+                first,
+                second,
+            } => MultiKeyError::<E>::SameSlot {
+                slot: *slot,
+                first: *first,
+                second: *second,
+            }
+            .fmt(f),
+            CacheError::LoadingSlot { slot } => HandleError::<E>::SameSlot { slot: *slot }.fmt(f),
+            CacheError::WouldBlock { slot } => HandleError::<E>::WouldBlock { slot: *slot }.fmt(f),
+            CacheError::DuplicateKey { key_hash } => BatchWriteError::<E>::DuplicateKey {
+                key_hash: *key_hash,
+            }
+            .fmt(f),
+        }
+    }
+}
+
+impl<E> Error for CacheError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CacheError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy, Tweaked, NOT_FOUND},
+        Cache, Overloaded,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_load_error_context() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let err = c.read(&0).err().unwrap();
+        assert_eq!(
+            (err.key_hash(), err.slot(), err.operation()),
+            (0, 0, Operation::Read)
+        );
+        assert_eq!(err.kind(), ErrorKind::Transient);
+        assert!(c.write(&4).is_ok());
+
+        let mut wb = c.write_batch();
+        let err = CacheError::from(wb.write(&0, |_| ()).err().unwrap());
+        wb.flush_all(|_| Ok::<_, ()>(())).unwrap();
+        let err = err.load_error().unwrap();
+        assert_eq!((err.slot(), err.operation()), (0, Operation::Batch));
+    }
+
+    #[test]
+    fn test_load_error_kind() {
+        let c = Cache::<_, TestHashBuilder>::new::<4>(Tweaked::<NOT_FOUND>::default());
+        assert_eq!(c.read(&0).err().unwrap().kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_cache_error() {
+        fn read_all(c: &Cache<TestStrategy, TestHashBuilder>) -> Result<usize, CacheError<()>> {
+            let len = c.read(&1)?.len();
+            let len = len + c.read_timeout(&2, Duration::ZERO)?.len();
+            Ok(len + c.read(&0)?.len())
+        }
+
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+        let err = read_all(&c).unwrap_err();
+        assert_eq!(err.load_error().map(|err| err.slot()), Some(0));
+    }
+
+    #[test]
+    fn test_errors_copy() {
+        fn assert_copy<T: Copy>() {}
+
+        assert_copy::<LoadError<ErrorKind>>();
+        assert_copy::<Overloaded>();
+        assert_copy::<TimeoutError<ErrorKind>>();
+        assert_copy::<MultiKeyError<ErrorKind>>();
+        assert_copy::<HandleError<ErrorKind>>();
+        assert_copy::<BatchWriteError<ErrorKind>>();
+        assert_copy::<CacheError<ErrorKind>>();
+    }
+}
@@ -0,0 +1,417 @@
+//! A C ABI over a byte-keyed, byte-valued [`Cache`].
+//!
+//! Values are produced by a C loader callback, which fills a
+//! [`GranularValue`] through [`granular_value_set`]. All functions return a
+//! status code: [`GRANULAR_OK`] on success, a negative `GRANULAR_ERR_*` code
+//! for errors detected by the cache itself, or the (positive) code returned by
+//! a callback when it failed. Callbacks must not operate the cache they were
+//! called by, which deadlocks.
+//!
+//! The C header lives in `include/granular_cache.h` and is generated with
+//! `cbindgen --config cbindgen.toml --output include/granular_cache.h`.
+
+use std::{ffi::c_void, slice};
+
+use crate::{Cache, CacheStrategy};
+
+/// The operation succeeded.
+pub const GRANULAR_OK: i32 = 0;
+
+/// A required pointer argument was null.
+pub const GRANULAR_ERR_NULL: i32 = -1;
+
+/// The write callback asked for the value to be discarded.
+pub const GRANULAR_ERR_ABORTED: i32 = -2;
+
+/// A callback failed with a negative code, which is reserved for the cache's
+/// own errors.
+pub const GRANULAR_ERR_CALLBACK: i32 = -3;
+
+/// Loads the value for the given key into `out`. Must return [`GRANULAR_OK`]
+/// on success; positive values are forwarded to the caller as is, and
+/// negative ones are reported as [`GRANULAR_ERR_CALLBACK`].
+///
+/// The loader runs while the cache is locked for loads, so it must not operate
+/// the same cache, which deadlocks.
+pub type GranularLoadFn =
+    extern "C" fn(ctx: *mut c_void, key: *const u8, key_len: usize, out: *mut GranularValue) -> i32;
+
+/// Inspects a value while the entry's read lock is held.
+pub type GranularReadFn = extern "C" fn(ctx: *mut c_void, val: *const u8, val_len: usize);
+
+/// Modifies a value while the entry's write lock is held. Returning anything
+/// other than [`GRANULAR_OK`] discards the value; positive values and
+/// [`GRANULAR_ERR_ABORTED`] are forwarded to the caller as is, and other
+/// negative ones are reported as [`GRANULAR_ERR_CALLBACK`].
+pub type GranularWriteFn = extern "C" fn(ctx: *mut c_void, val: *mut GranularValue) -> i32;
+
+/// An opaque, growable byte buffer holding a cached value.
+pub struct GranularValue(Vec<u8>);
+
+/// An opaque handle to a byte-keyed cache.
+pub struct GranularCache(Cache<FfiStrategy>);
+
+/// The cached entry, which also keeps the key to detect slot conflicts.
+struct FfiEntry {
+    key: Vec<u8>,
+    val: GranularValue,
+}
+
+struct FfiStrategy {
+    load: GranularLoadFn,
+    ctx: *mut c_void,
+}
+
+// SAFETY: Callers of `granular_cache_new` guarantee that `ctx` may be used
+// from any thread that operates the cache.
+unsafe impl Send for FfiStrategy {}
+
+impl CacheStrategy for FfiStrategy {
+    type Key = Vec<u8>;
+    type Val = FfiEntry;
+    type Err = i32;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        let mut val = GranularValue(Vec::new());
+        match (self.load)(self.ctx, key.as_ptr(), key.len(), &mut val) {
+            GRANULAR_OK => Ok(FfiEntry {
+                key: key.clone(),
+                val,
+            }),
+            code => Err(callback_code(code)),
+        }
+    }
+
+    fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+        val.key != *key
+    }
+}
+
+/// Maps the failure code returned by a callback to the one returned to the
+/// caller, keeping it apart from the cache's own codes.
+fn callback_code(code: i32) -> i32 {
+    if code < 0 {
+        GRANULAR_ERR_CALLBACK
+    } else {
+        code
+    }
+}
+
+/// Copies the given bytes as a Rust-owned vector.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, unless `len` is zero.
+unsafe fn to_vec(ptr: *const u8, len: usize) -> Vec<u8> {
+    if len == 0 {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(ptr, len).to_vec()
+    }
+}
+
+/// Creates a new cache with `capacity` slots, which must be non-zero, loading
+/// missing values through `load`. Returns null if `capacity` is zero.
+///
+/// # Safety
+///
+/// `ctx` is passed to `load` as is and must be usable from every thread that
+/// operates the returned cache.
+#[no_mangle]
+pub unsafe extern "C" fn granular_cache_new(
+    capacity: usize,
+    load: GranularLoadFn,
+    ctx: *mut c_void,
+) -> *mut GranularCache {
+    if capacity == 0 {
+        return std::ptr::null_mut();
+    }
+    let cache = Cache::with_capacity(FfiStrategy { load, ctx }, capacity);
+    Box::into_raw(Box::new(GranularCache(cache)))
+}
+
+/// Destroys a cache created by [`granular_cache_new`]. Null is a no-op.
+///
+/// # Safety
+///
+/// `cache` must have been returned by [`granular_cache_new`], must not be used
+/// afterwards and no other thread may be operating it.
+#[no_mangle]
+pub unsafe extern "C" fn granular_cache_free(cache: *mut GranularCache) {
+    if !cache.is_null() {
+        drop(Box::from_raw(cache));
+    }
+}
+
+/// Acquires the value for the given key for read, loading it if needed, and
+/// passes it to `read` while the read lock is held.
+///
+/// # Safety
+///
+/// `cache` must be a live cache handle and `key` must be valid for reads of
+/// `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn granular_cache_read(
+    cache: *const GranularCache,
+    key: *const u8,
+    key_len: usize,
+    read: GranularReadFn,
+    ctx: *mut c_void,
+) -> i32 {
+    let Some(cache) = cache.as_ref() else {
+        return GRANULAR_ERR_NULL;
+    };
+    if key.is_null() && key_len != 0 {
+        return GRANULAR_ERR_NULL;
+    }
+    match cache.0.read(&to_vec(key, key_len)) {
+        Ok(entry) => {
+            let val = &entry.val.0;
+            read(ctx, val.as_ptr(), val.len());
+            GRANULAR_OK
+        }
+        Err(err) => err.into_err(),
+    }
+}
+
+/// Acquires the value for the given key for write, loading it if needed, and
+/// passes it to `write` while the write lock is held.
+///
+/// If `write` fails, the entry is discarded under the same write lock, so that
+/// no reader sees the value it may have left half-modified, and the next
+/// access reloads it.
+///
+/// # Safety
+///
+/// `cache` must be a live cache handle and `key` must be valid for reads of
+/// `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn granular_cache_write(
+    cache: *const GranularCache,
+    key: *const u8,
+    key_len: usize,
+    write: GranularWriteFn,
+    ctx: *mut c_void,
+) -> i32 {
+    let Some(cache) = cache.as_ref() else {
+        return GRANULAR_ERR_NULL;
+    };
+    if key.is_null() && key_len != 0 {
+        return GRANULAR_ERR_NULL;
+    }
+    let key = to_vec(key, key_len);
+    let mut entry = match cache.0.entry(&key).or_load_entry() {
+        Ok(entry) => entry,
+        Err(err) => return err.into_err(),
+    };
+    match write(ctx, &mut entry.get_mut().val) {
+        GRANULAR_OK => GRANULAR_OK,
+        code => {
+            entry.remove();
+            if code == GRANULAR_ERR_ABORTED {
+                code
+            } else {
+                callback_code(code)
+            }
+        }
+    }
+}
+
+/// Drops the value for the given key, if resident. Returns [`GRANULAR_OK`]
+/// whether or not the key was resident.
+///
+/// # Safety
+///
+/// `cache` must be a live cache handle and `key` must be valid for reads of
+/// `key_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn granular_cache_invalidate(
+    cache: *const GranularCache,
+    key: *const u8,
+    key_len: usize,
+) -> i32 {
+    let Some(cache) = cache.as_ref() else {
+        return GRANULAR_ERR_NULL;
+    };
+    if key.is_null() && key_len != 0 {
+        return GRANULAR_ERR_NULL;
+    }
+    cache.0.invalidate(&to_vec(key, key_len));
+    GRANULAR_OK
+}
+
+/// Stores a pointer to the value's bytes in `data` and their length in `len`.
+///
+/// # Safety
+///
+/// `val` must be a value handed out by the cache, and `data` and `len` must
+/// be valid for writes. The stored pointer is only valid until the value is
+/// modified.
+#[no_mangle]
+pub unsafe extern "C" fn granular_value_data(
+    val: *const GranularValue,
+    data: *mut *const u8,
+    len: *mut usize,
+) -> i32 {
+    let Some(val) = val.as_ref() else {
+        return GRANULAR_ERR_NULL;
+    };
+    if data.is_null() || len.is_null() {
+        return GRANULAR_ERR_NULL;
+    }
+    *data = val.0.as_ptr();
+    *len = val.0.len();
+    GRANULAR_OK
+}
+
+/// Replaces the value's bytes with a copy of the given ones.
+///
+/// # Safety
+///
+/// `val` must be a value handed out by the cache and `data` must be valid for
+/// reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn granular_value_set(
+    val: *mut GranularValue,
+    data: *const u8,
+    len: usize,
+) -> i32 {
+    let Some(val) = val.as_mut() else {
+        return GRANULAR_ERR_NULL;
+    };
+    if data.is_null() && len != 0 {
+        return GRANULAR_ERR_NULL;
+    }
+    val.0 = to_vec(data, len);
+    GRANULAR_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ptr,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+
+    extern "C" fn load(
+        ctx: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        out: *mut GranularValue,
+    ) -> i32 {
+        let count = unsafe { &*(ctx as *const AtomicU32) };
+        count.fetch_add(1, Ordering::SeqCst);
+        let key = unsafe { slice::from_raw_parts(key, key_len) };
+        match key {
+            b"missing" => return 7,
+            b"negative" => return -7,
+            _ => {}
+        }
+        let mut val = key.to_vec();
+        val.extend_from_slice(b"-val");
+        unsafe { granular_value_set(out, val.as_ptr(), val.len()) }
+    }
+
+    extern "C" fn read(ctx: *mut c_void, val: *const u8, val_len: usize) {
+        let out = unsafe { &mut *(ctx as *mut Vec<u8>) };
+        *out = unsafe { to_vec(val, val_len) };
+    }
+
+    extern "C" fn append(_: *mut c_void, val: *mut GranularValue) -> i32 {
+        let (mut data, mut len) = (ptr::null(), 0);
+        let code = unsafe { granular_value_data(val, &mut data, &mut len) };
+        assert_eq!(code, GRANULAR_OK);
+        let mut new = unsafe { to_vec(data, len) };
+        new.extend_from_slice(b"-mod");
+        unsafe { granular_value_set(val, new.as_ptr(), new.len()) }
+    }
+
+    extern "C" fn abort(_: *mut c_void, _: *mut GranularValue) -> i32 {
+        GRANULAR_ERR_ABORTED
+    }
+
+    extern "C" fn fail(_: *mut c_void, val: *mut GranularValue) -> i32 {
+        let code = unsafe { granular_value_set(val, b"junk".as_ptr(), 4) };
+        assert_eq!(code, GRANULAR_OK);
+        -1
+    }
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        let count = AtomicU32::new(0);
+        let ctx = &count as *const AtomicU32 as *mut c_void;
+        let mut out = Vec::<u8>::new();
+        let out_ctx = &mut out as *mut Vec<u8> as *mut c_void;
+
+        unsafe {
+            let c = granular_cache_new(4, load, ctx);
+            assert!(!c.is_null());
+
+            assert_eq!(
+                granular_cache_read(c, b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_OK
+            );
+            assert_eq!(out, b"k-val");
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+
+            assert_eq!(
+                granular_cache_write(c, b"k".as_ptr(), 1, append, ptr::null_mut()),
+                GRANULAR_OK
+            );
+            assert_eq!(
+                granular_cache_read(c, b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_OK
+            );
+            assert_eq!(out, b"k-val-mod");
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+
+            let code = granular_cache_write(c, b"k".as_ptr(), 1, abort, ptr::null_mut());
+            assert_eq!(code, GRANULAR_ERR_ABORTED);
+            assert_eq!(
+                granular_cache_read(c, b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_OK
+            );
+            assert_eq!(out, b"k-val");
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+
+            assert_eq!(granular_cache_invalidate(c, b"k".as_ptr(), 1), GRANULAR_OK);
+            assert_eq!(
+                granular_cache_read(c, b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_OK
+            );
+            assert_eq!(count.load(Ordering::SeqCst), 3);
+
+            let code = granular_cache_write(c, b"k".as_ptr(), 1, fail, ptr::null_mut());
+            assert_eq!(code, GRANULAR_ERR_CALLBACK);
+            assert_eq!(
+                granular_cache_read(c, b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_OK
+            );
+            assert_eq!(out, b"k-val");
+            assert_eq!(count.load(Ordering::SeqCst), 4);
+
+            assert_eq!(
+                granular_cache_read(c, b"missing".as_ptr(), 7, read, out_ctx),
+                7
+            );
+            assert_eq!(
+                granular_cache_read(c, b"negative".as_ptr(), 8, read, out_ctx),
+                GRANULAR_ERR_CALLBACK
+            );
+            let mut len = 0;
+            assert_eq!(
+                granular_value_data(ptr::null(), &mut ptr::null(), &mut len),
+                GRANULAR_ERR_NULL
+            );
+            assert_eq!(
+                granular_cache_read(ptr::null(), b"k".as_ptr(), 1, read, out_ctx),
+                GRANULAR_ERR_NULL
+            );
+
+            granular_cache_free(c);
+            assert!(granular_cache_new(0, load, ctx).is_null());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    hash::{BuildHasher, Hash},
+    mem,
+    sync::{Mutex, RwLock},
+};
+
+use crate::{Cache, CacheStrategy, Slot};
+
+/// The memory used by a cache's slots, in bytes, as reported by
+/// [`Cache::mem_footprint`].
+///
+/// Values are stored inline in the slots, so every slot takes room for one
+/// whether it holds it or not. The cache's bookkeeping structures (e.g., the
+/// event journal, or the collision tracker's per-slot state) aren't accounted
+/// for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemFootprint {
+    /// The number of slots.
+    pub slots: usize,
+    /// The bytes taken by the slots' locks.
+    pub locks: usize,
+    /// The bytes taken by the slots' metadata (e.g., key hashes, generations
+    /// and epochs), including padding.
+    pub metadata: usize,
+    /// The bytes taken by the values stored inline.
+    pub values: usize,
+    /// The bytes owned by the resident values out of line, as weighed by
+    /// [`Cache::mem_footprint_with`].
+    pub heap: usize,
+}
+
+impl MemFootprint {
+    /// Returns the bytes taken by each slot beyond its inline value.
+    pub fn per_slot_overhead(&self) -> usize {
+        (self.locks + self.metadata) / self.slots
+    }
+
+    /// Returns the total bytes.
+    pub fn total(&self) -> usize {
+        self.locks + self.metadata + self.values + self.heap
+    }
+}
+
+impl<S, H> Cache<S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    /// Returns the memory used by the slots, without weighing the resident
+    /// values' out-of-line allocations (see [`Cache::mem_footprint_with`]).
+    pub fn mem_footprint(&self) -> MemFootprint {
+        let slots = self.entries.len();
+        // Each slot has its lock and its upgrade token.
+        let lock = mem::size_of::<RwLock<()>>() + mem::size_of::<Mutex<()>>();
+        let val = mem::size_of::<S::Val>();
+        let metadata = mem::size_of::<Slot<S::Val>>() - lock - val;
+        MemFootprint {
+            slots,
+            locks: slots * lock,
+            metadata: slots * metadata,
+            values: slots * val,
+            heap: 0,
+        }
+    }
+
+    /// Like [`Cache::mem_footprint`], but also weighs the out-of-line
+    /// allocations of each resident value with the given function, e.g. a
+    /// string's capacity. Each slot is locked for read while weighed.
+    pub fn mem_footprint_with<F>(&self, mut weigh: F) -> MemFootprint
+    where
+        F: FnMut(&S::Val) -> usize,
+    {
+        let mut footprint = self.mem_footprint();
+        for slot in self.entries.iter() {
+            if let Some(val) = slot.read().as_ref().and_then(|entry| entry.val.as_ref()) {
+                footprint.heap += weigh(val);
+            }
+        }
+        footprint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    /// The most bytes a slot may take beyond its inline value. Raise it
+    /// deliberately, if ever.
+    const MAX_SLOT_OVERHEAD: usize = 64;
+
+    #[test]
+    fn test_slot_overhead() {
+        fn overhead<V>() -> usize {
+            mem::size_of::<Slot<V>>() - mem::size_of::<V>()
+        }
+
+        assert!(overhead::<u8>() <= MAX_SLOT_OVERHEAD);
+        assert!(overhead::<u64>() <= MAX_SLOT_OVERHEAD);
+        assert!(overhead::<String>() <= MAX_SLOT_OVERHEAD);
+        assert!(overhead::<[u8; 4096]>() <= MAX_SLOT_OVERHEAD);
+    }
+
+    #[test]
+    fn test_mem_footprint() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&3).unwrap());
+        let footprint = c.mem_footprint_with(|val| val.capacity());
+        assert_eq!(footprint.slots, 4);
+        assert_eq!(footprint.values, 4 * mem::size_of::<String>());
+        assert_eq!(footprint.heap, "1one".len() + "3three".len());
+        assert!(footprint.per_slot_overhead() <= MAX_SLOT_OVERHEAD);
+        assert_eq!(
+            footprint.total(),
+            4 * mem::size_of::<Slot<String>>() + footprint.heap
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! The Fx hasher (as used by rustc), a fast non-cryptographic hasher which
+//! suits small integer keys, enabled by the `fxhash` feature.
+//!
+//! It offers no protection against collision attacks, so it must not be used
+//! with keys which untrusted parties may choose.
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::Cache;
+
+/// A [`Cache`] using the Fx hasher.
+pub type FxCache<S> = Cache<S, FxBuildHasher>;
+
+/// Builds [`FxHasher`]s.
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The Fx hasher.
+#[derive(Clone, Copy, Default)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        for &byte in chunks.remainder() {
+            self.add(u64::from(byte));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add(u64::from(i));
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.add(u64::from(i));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.add(u64::from(i));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::BuildHasher;
+
+    use crate::test_utils::TestStrategy;
+
+    use super::*;
+
+    #[test]
+    fn test_fx_cache() {
+        let b = FxBuildHasher::default();
+        assert_eq!(b.hash_one(1u32), SEED);
+        assert_ne!(b.hash_one(1u32), b.hash_one(2u32));
+
+        let c = FxCache::new::<4>(TestStrategy::default());
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(c.clone_strategy().count(), 1);
+    }
+}
@@ -0,0 +1,213 @@
+//! Tracking of outstanding guards and of the last writer of each slot,
+//! enabled by the `debug-guards` feature.
+//!
+//! When the feature is disabled, the registry and its tokens are zero-sized
+//! and every operation is a no-op.
+
+pub(crate) use self::imp::{GuardRegistry, GuardToken};
+#[cfg(feature = "debug-guards")]
+pub use self::imp::{LastWriter, OutstandingGuard};
+
+/// The kind of lock a guard holds over its slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuardKind {
+    Read,
+    Write,
+}
+
+#[cfg(feature = "debug-guards")]
+mod imp {
+    use std::{
+        backtrace::Backtrace,
+        collections::HashMap,
+        fmt,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, ThreadId},
+        time::{Duration, Instant},
+    };
+
+    use tracing::warn;
+
+    use super::GuardKind;
+
+    /// A guard which is currently alive, along with where it was created.
+    #[derive(Clone)]
+    pub struct OutstandingGuard {
+        pub slot: usize,
+        pub kind: GuardKind,
+        pub created_at: Instant,
+        pub backtrace: Arc<Backtrace>,
+    }
+
+    impl OutstandingGuard {
+        /// Returns for how long the guard has been alive.
+        pub fn age(&self) -> Duration {
+            self.created_at.elapsed()
+        }
+    }
+
+    impl fmt::Debug for OutstandingGuard {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{:?} guard over slot {}, alive for {:?}, created at:\n{}",
+                self.kind,
+                self.slot,
+                self.age(),
+                self.backtrace
+            )
+        }
+    }
+
+    /// The thread which last stored a value in a slot or acquired it for
+    /// write, and when it did.
+    #[derive(Clone, Debug)]
+    pub struct LastWriter {
+        pub thread: ThreadId,
+        pub thread_name: Option<String>,
+        pub written_at: Instant,
+    }
+
+    impl LastWriter {
+        /// Returns for how long ago the slot was written.
+        pub fn age(&self) -> Duration {
+            self.written_at.elapsed()
+        }
+    }
+
+    pub(crate) struct GuardRegistry {
+        next_id: AtomicU64,
+        live: Mutex<HashMap<u64, OutstandingGuard>>,
+        write_lease: Mutex<Option<Duration>>,
+        last_writers: Mutex<HashMap<usize, LastWriter>>,
+    }
+
+    impl GuardRegistry {
+        pub(crate) fn new() -> GuardRegistry {
+            GuardRegistry {
+                next_id: AtomicU64::new(0),
+                live: Mutex::new(HashMap::new()),
+                write_lease: Mutex::new(None),
+                last_writers: Mutex::new(HashMap::new()),
+            }
+        }
+
+        pub(crate) fn track(&self, slot: usize, kind: GuardKind) -> GuardToken<'_> {
+            if kind == GuardKind::Write {
+                self.record_writer(slot);
+            }
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let guard = OutstandingGuard {
+                slot,
+                kind,
+                created_at: Instant::now(),
+                backtrace: Arc::new(Backtrace::force_capture()),
+            };
+            self.live().insert(id, guard);
+            GuardToken { registry: self, id }
+        }
+
+        /// Returns the outstanding guards, oldest first.
+        pub(crate) fn outstanding(&self) -> Vec<OutstandingGuard> {
+            let mut guards: Vec<_> = self.live().values().cloned().collect();
+            guards.sort_by_key(|g| g.created_at);
+            guards
+        }
+
+        /// Records the current thread as the last writer of the given slot.
+        pub(crate) fn record_writer(&self, slot: usize) {
+            let current = thread::current();
+            let writer = LastWriter {
+                thread: current.id(),
+                thread_name: current.name().map(str::to_owned),
+                written_at: Instant::now(),
+            };
+            self.last_writers().insert(slot, writer);
+        }
+
+        pub(crate) fn last_writer(&self, slot: usize) -> Option<LastWriter> {
+            self.last_writers().get(&slot).cloned()
+        }
+
+        pub(crate) fn set_write_lease(&self, lease: Option<Duration>) {
+            *self.write_lease.lock().unwrap_or_else(|e| e.into_inner()) = lease;
+        }
+
+        /// Returns the outstanding write guards which outlived the write lease,
+        /// oldest first.
+        pub(crate) fn expired_write_leases(&self) -> Vec<OutstandingGuard> {
+            let mut guards = self.outstanding();
+            guards.retain(|g| self.outlived_lease(g));
+            guards
+        }
+
+        fn outlived_lease(&self, guard: &OutstandingGuard) -> bool {
+            let lease = *self.write_lease.lock().unwrap_or_else(|e| e.into_inner());
+            guard.kind == GuardKind::Write && lease.is_some_and(|lease| guard.age() > lease)
+        }
+
+        fn live(&self) -> std::sync::MutexGuard<'_, HashMap<u64, OutstandingGuard>> {
+            // The map is always left coherent, so poisoning can be ignored.
+            self.live.lock().unwrap_or_else(|e| e.into_inner())
+        }
+
+        fn last_writers(&self) -> std::sync::MutexGuard<'_, HashMap<usize, LastWriter>> {
+            self.last_writers.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    /// Unregisters its guard from the registry when dropped (i.e., it is only
+    /// kept for its `Drop` implementation).
+    pub(crate) struct GuardToken<'a> {
+        registry: &'a GuardRegistry,
+        id: u64,
+    }
+
+    impl GuardToken<'_> {
+        /// Records that the guard now holds a read lock over its slot.
+        pub(crate) fn downgrade(&mut self) {
+            if let Some(guard) = self.registry.live().get_mut(&self.id) {
+                guard.kind = GuardKind::Read;
+            }
+        }
+    }
+
+    impl Drop for GuardToken<'_> {
+        fn drop(&mut self) {
+            let guard = self.registry.live().remove(&self.id);
+            if let Some(guard) = guard.filter(|g| self.registry.outlived_lease(g)) {
+                warn!("write guard released after its lease expired: {guard:?}");
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "debug-guards"))]
+mod imp {
+    use std::marker::PhantomData;
+
+    use super::GuardKind;
+
+    pub(crate) struct GuardRegistry;
+
+    impl GuardRegistry {
+        pub(crate) fn new() -> GuardRegistry {
+            GuardRegistry
+        }
+
+        pub(crate) fn track(&self, _slot: usize, _kind: GuardKind) -> GuardToken<'_> {
+            GuardToken(PhantomData)
+        }
+
+        pub(crate) fn record_writer(&self, _slot: usize) {}
+    }
+
+    pub(crate) struct GuardToken<'a>(PhantomData<&'a ()>);
+
+    impl GuardToken<'_> {
+        pub(crate) fn downgrade(&mut self) {}
+    }
+}
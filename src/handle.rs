@@ -0,0 +1,244 @@
+use std::{
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+    sync::RwLockWriteGuard,
+};
+
+use crate::{
+    misuse, Cache, CacheStrategy, Entry, GuardKind, LoadError, LoadHint, Operation, ReadRef,
+};
+
+/// A view over the cache handed to [`CacheStrategy::load_with`], so that a
+/// strategy may read other keys while it computes a value.
+///
+/// The strategy can't go through the [`Cache`] itself, since the cache's
+/// strategy lock is held during the load. Instead, it passes itself to
+/// [`CacheHandle::read`], which loads missing keys using it directly.
+///
+/// To rule out deadlocks, the handle never blocks: reading a key whose slot
+/// is being loaded further up the chain fails with [`HandleError::SameSlot`],
+/// after being reported as misuse (see [`MisusePolicy`]), and reading a slot
+/// which is locked by someone else fails with [`HandleError::WouldBlock`].
+///
+/// [`MisusePolicy`]: crate::MisusePolicy
+pub struct CacheHandle<'c, S, H>
+where
+    S: CacheStrategy,
+{
+    cache: &'c Cache<S, H>,
+    loading: Vec<usize>,
+    hint: LoadHint,
+}
+
+impl<'c, S, H> CacheHandle<'c, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    pub(crate) fn new(
+        cache: &'c Cache<S, H>,
+        slot: usize,
+        hint: LoadHint,
+    ) -> CacheHandle<'c, S, H> {
+        CacheHandle {
+            cache,
+            loading: vec![slot],
+            hint,
+        }
+    }
+
+    /// Returns why and how urgently the key is being loaded. Keys read
+    /// through the handle are loaded with the same hint.
+    pub fn hint(&self) -> LoadHint {
+        self.hint
+    }
+
+    /// Acquires the value by the given key, for read, loading it with the
+    /// given strategy if needed.
+    pub fn read(
+        &self,
+        strategy: &mut S,
+        key: &S::Key,
+    ) -> Result<ReadRef<'c, S::Val>, HandleError<S::Err>> {
+        let cache = self.cache;
+        let hash = cache.hash(key);
+        let slot = cache.slot_for_hash(hash);
+        if self.loading.contains(&slot) {
+            misuse::report(format_args!(
+                "recursive load of slot {slot}, which is being loaded"
+            ));
+            return Err(HandleError::SameSlot { slot });
+        }
+
+        let Some(guard) = cache.slot_at(slot).try_read() else {
+            return Err(HandleError::WouldBlock { slot });
+        };
+        if !cache.check_miss(key, hash, slot, Operation::Read, &guard)? {
+            return Ok(ReadRef::new(
+                guard,
+                cache.guards.track(slot, GuardKind::Read),
+            ));
+        }
+        drop(guard);
+
+        let Some(mut guard) = cache.slot_at(slot).try_write() else {
+            return Err(HandleError::WouldBlock { slot });
+        };
+        if cache.check_miss(key, hash, slot, Operation::Read, &guard)? {
+            let stale = cache.is_stale(key, hash, &guard);
+            let mut loading = self.loading.clone();
+            loading.push(slot);
+            let nested = CacheHandle {
+                cache,
+                loading,
+                hint: self.hint,
+            };
+            let displaced = cache.store(key, hash, Operation::Read, &mut guard, || {
+                strategy.load_with(key, &nested)
+            })?;
+            if cfg!(debug_assertions) {
+                strategy.debug_validate(key, Entry::val(&guard));
+            }
+            if let Some(stale) = displaced.filter(|_| stale) {
+                cache.report_reload(strategy, key, &stale, Entry::val(&guard));
+            }
+        }
+        let guard = RwLockWriteGuard::downgrade(guard);
+        Ok(ReadRef::new(
+            guard,
+            cache.guards.track(slot, GuardKind::Read),
+        ))
+    }
+}
+
+/// An error when reading through a [`CacheHandle`].
+///
+/// Like the cache's other errors, it is `Copy` whenever the strategy's error
+/// is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandleError<E> {
+    /// The key maps to a slot which is being loaded further up the chain of
+    /// recursive loads.
+    SameSlot { slot: usize },
+    /// The key maps to a slot which is currently locked.
+    WouldBlock { slot: usize },
+    /// The strategy failed to load the key.
+    Load(LoadError<E>),
+}
+
+impl<E> From<LoadError<E>> for HandleError<E> {
+    fn from(err: LoadError<E>) -> Self {
+        HandleError::Load(err)
+    }
+}
+
+impl<E> fmt::Display for HandleError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandleError::SameSlot { slot } => {
+                write!(f, "recursive load of slot {slot}, which is being loaded")
+            }
+            HandleError::WouldBlock { slot } => {
+                write!(f, "recursive load of slot {slot}, which is locked")
+            }
+            HandleError::Load(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E> Error for HandleError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HandleError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        MisusePolicy,
+    };
+
+    use super::*;
+
+    /// Assembles `n` from the values of the keys `n - 1` down to `1`.
+    #[derive(Clone, Default)]
+    struct Assembling(TestStrategy);
+
+    impl CacheStrategy for Assembling {
+        type Key = u32;
+        type Val = String;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.0.load(key)
+        }
+
+        fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+            !val.starts_with(&format!("{key}:"))
+        }
+
+        fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+            a == b
+        }
+
+        fn load_with<H>(
+            &mut self,
+            key: &Self::Key,
+            cache: &CacheHandle<'_, Self, H>,
+        ) -> Result<Self::Val, Self::Err>
+        where
+            H: BuildHasher,
+        {
+            let mut val = format!("{key}:");
+            if *key > 1 {
+                let prev = cache.read(self, &(key - 1)).map_err(|_| ())?;
+                val.push_str(&prev);
+            }
+            self.0.load(key)?;
+            Ok(val)
+        }
+    }
+
+    #[test]
+    fn test_recursive_load() {
+        let c = Cache::<Assembling, TestHashBuilder>::new::<8>(Assembling::default());
+
+        assert_eq!(&*c.read(&3).unwrap(), "3:2:1:");
+        assert_eq!(c.clone_strategy().0.count(), 3);
+        assert_eq!(&*c.read(&2).unwrap(), "2:1:");
+        assert_eq!(c.clone_strategy().0.count(), 3);
+    }
+
+    #[test]
+    fn test_recursive_reload() {
+        let c = Cache::<Assembling, TestHashBuilder>::new::<8>(Assembling::default());
+
+        drop(c.read(&3).unwrap());
+        c.bump_epoch();
+        // Every key below `4` is reloaded through the handle, identically.
+        assert_eq!(&*c.read(&4).unwrap(), "4:3:2:1:");
+        assert_eq!(c.identical_reloads(), 3);
+        assert_eq!(c.clone_strategy().0.count(), 7);
+    }
+
+    #[test]
+    fn test_recursive_load_same_slot() {
+        let c = Cache::<Assembling, TestHashBuilder>::new::<2>(Assembling::default());
+
+        // `3` and `1` share a slot, so loading `3` can't read `1`.
+        misuse::with_policy(MisusePolicy::Log, || assert!(c.read(&3).is_err()));
+        assert_eq!(&*c.read(&2).unwrap(), "2:1:");
+    }
+}
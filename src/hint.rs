@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use crate::Operation;
+
+/// Why and how urgently a key is being loaded, as handed to the strategy
+/// through [`CacheHandle::hint`], so that it may pick a cheaper path for
+/// loads nobody waits on (e.g., reading from a replica).
+///
+/// [`CacheHandle::hint`]: crate::CacheHandle::hint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadHint {
+    /// For how long the load is expected to take at most, as set by
+    /// [`Cache::set_load_budget`].
+    ///
+    /// [`Cache::set_load_budget`]: crate::Cache::set_load_budget
+    pub budget: Option<Duration>,
+    pub reason: LoadReason,
+}
+
+/// Why a key is being loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LoadReason {
+    /// The key was accessed and isn't resident.
+    Miss,
+    /// The key's value is resident but stale, e.g. from a past epoch (see
+    /// [`Cache::bump_epoch`]).
+    ///
+    /// [`Cache::bump_epoch`]: crate::Cache::bump_epoch
+    Refresh,
+    /// The key is being loaded ahead of any access to it, e.g. by
+    /// [`Cache::warm`].
+    ///
+    /// [`Cache::warm`]: crate::Cache::warm
+    Prefetch,
+}
+
+impl LoadReason {
+    /// Returns why a key is loaded on behalf of the given operation, given
+    /// whether its slot holds a stale value for it.
+    pub(crate) fn of(op: Operation, stale: bool) -> LoadReason {
+        match op {
+            Operation::Preload => LoadReason::Prefetch,
+            _ if stale => LoadReason::Refresh,
+            _ => LoadReason::Miss,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        hash::BuildHasher,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache, CacheHandle, CacheStrategy,
+    };
+
+    use super::*;
+
+    /// Logs the hint of every load.
+    #[derive(Clone, Default)]
+    struct Hinted {
+        inner: TestStrategy,
+        hints: Arc<Mutex<Vec<LoadHint>>>,
+    }
+
+    impl CacheStrategy for Hinted {
+        type Key = u32;
+        type Val = String;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.inner.load(key)
+        }
+
+        fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+            TestStrategy::match_kv(key, val)
+        }
+
+        fn load_with<H>(
+            &mut self,
+            key: &Self::Key,
+            cache: &CacheHandle<'_, Self, H>,
+        ) -> Result<Self::Val, Self::Err>
+        where
+            H: BuildHasher,
+        {
+            self.hints.lock().unwrap().push(cache.hint());
+            self.load(key)
+        }
+    }
+
+    #[test]
+    fn test_load_hints() {
+        let s = Hinted::default();
+        let hints = s.hints.clone();
+        let c = Cache::<Hinted, TestHashBuilder>::new::<4>(s);
+
+        c.warm([1]);
+        c.set_load_budget(Some(Duration::from_millis(5)));
+        drop(c.read(&2).unwrap());
+        c.bump_epoch();
+        drop(c.read(&2).unwrap());
+
+        let hint = |budget, reason| LoadHint { budget, reason };
+        let budget = Some(Duration::from_millis(5));
+        assert_eq!(
+            *hints.lock().unwrap(),
+            [
+                hint(None, LoadReason::Prefetch),
+                hint(budget, LoadReason::Miss),
+                hint(budget, LoadReason::Refresh),
+            ]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::SystemTime,
+};
+
+use crate::Operation;
+
+/// What happened to the slot an operation accessed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EventOutcome {
+    /// The slot already held the key.
+    Hit,
+    /// The slot was empty and a value of the key was stored into it, however
+    /// the event's [`Operation`] produced it (e.g., by a load, an insert or an
+    /// external load).
+    Loaded,
+    /// Like `Loaded`, but the slot wasn't empty: the value of another key, a
+    /// stale value of the key (e.g., one predating [`Cache::bump_epoch`]) or
+    /// a remembered absence was replaced.
+    ///
+    /// [`Cache::bump_epoch`]: crate::Cache::bump_epoch
+    Replaced,
+    /// The key had to be loaded, but the strategy failed.
+    LoadFailed,
+}
+
+/// A cache decision, as recorded by the event journal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheEvent {
+    pub operation: Operation,
+    pub key_hash: u64,
+    pub slot: usize,
+    pub outcome: EventOutcome,
+    pub at: SystemTime,
+}
+
+/// A fixed-size ring of the most recent cache events, disabled by default.
+pub(crate) struct EventJournal {
+    enabled: AtomicBool,
+    ring: Mutex<Ring>,
+}
+
+struct Ring {
+    events: VecDeque<CacheEvent>,
+    capacity: usize,
+}
+
+impl EventJournal {
+    pub(crate) fn new() -> EventJournal {
+        EventJournal {
+            enabled: AtomicBool::new(false),
+            ring: Mutex::new(Ring {
+                events: VecDeque::new(),
+                capacity: 0,
+            }),
+        }
+    }
+
+    /// Keeps up to `capacity` events from now on, or disables the journal if
+    /// `capacity` is zero. Events already recorded are kept while they fit.
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut ring = self.ring();
+        ring.capacity = capacity;
+        while ring.events.len() > capacity {
+            ring.events.pop_front();
+        }
+        ring.events.shrink_to(capacity);
+        self.enabled.store(capacity > 0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record(
+        &self,
+        operation: Operation,
+        key_hash: u64,
+        slot: usize,
+        outcome: EventOutcome,
+    ) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let event = CacheEvent {
+            operation,
+            key_hash,
+            slot,
+            outcome,
+            at: SystemTime::now(),
+        };
+        let mut ring = self.ring();
+        if ring.capacity == 0 {
+            return;
+        }
+        if ring.events.len() == ring.capacity {
+            ring.events.pop_front();
+        }
+        ring.events.push_back(event);
+    }
+
+    /// Returns the recorded events, oldest first.
+    pub(crate) fn events(&self) -> Vec<CacheEvent> {
+        self.ring().events.iter().cloned().collect()
+    }
+
+    fn ring(&self) -> MutexGuard<'_, Ring> {
+        // The ring is always left coherent, so poisoning can be ignored.
+        self.ring.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_recent_events() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        assert!(c.recent_events().is_empty());
+
+        c.set_event_journal_capacity(3);
+        drop(c.read(&1).unwrap());
+        drop(c.write(&2).unwrap());
+        drop(c.read(&5).unwrap());
+        assert!(c.read(&0).is_err());
+
+        let events: Vec<_> = c
+            .recent_events()
+            .into_iter()
+            .map(|e| (e.operation, e.key_hash, e.slot, e.outcome))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (Operation::Write, 2, 2, EventOutcome::Loaded),
+                (Operation::Read, 5, 1, EventOutcome::Replaced),
+                (Operation::Read, 0, 0, EventOutcome::LoadFailed),
+            ]
+        );
+
+        c.set_event_journal_capacity(1);
+        assert_eq!(c.recent_events().len(), 1);
+        c.set_event_journal_capacity(0);
+        drop(c.read(&1).unwrap());
+        assert!(c.recent_events().is_empty());
+    }
+}
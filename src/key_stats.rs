@@ -0,0 +1,288 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::RandomState, HashMap},
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+/// How many keys are tracked at once by the key stats sampler.
+const MAX_SAMPLED_KEYS: usize = 64;
+
+/// How many sampled accesses a thread buffers before flushing them into the
+/// shared reservoir.
+const FLUSH_EVERY: usize = 32;
+
+/// The hits and misses of a sampled key, counted over the accesses sampled
+/// since the key was picked by the sampler.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyStats {
+    pub key_hash: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Tracks the hits and misses of a reservoir sample of the accessed keys, so
+/// that frequently accessed keys are the likeliest to be tracked. Disabled by
+/// default.
+///
+/// Sampled accesses are buffered by each thread and flushed into the shared
+/// reservoir in batches, so that the hot path doesn't contend over it.
+pub(crate) struct KeyStatsSampler {
+    every: AtomicU32,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    random: RandomState,
+    reservoir: Mutex<Reservoir>,
+}
+
+#[derive(Default)]
+struct Reservoir {
+    /// How many times the reservoir was reset, so that accesses buffered
+    /// before a reset can be told apart.
+    resets: u64,
+    /// How many accesses were sampled.
+    sampled: u64,
+    keys: Vec<KeyStats>,
+    /// The index of each tracked key in `keys`, by hash.
+    positions: HashMap<u64, usize>,
+}
+
+/// The accesses buffered by the current thread for a sampler.
+struct Pending {
+    shared: Arc<Shared>,
+    /// The reservoir's resets when the accesses were buffered.
+    resets: u64,
+    /// How many accesses the thread made, sampled or not.
+    seen: u32,
+    accesses: Vec<(u64, bool)>,
+}
+
+thread_local! {
+    static PENDING: RefCell<Option<Pending>> = const { RefCell::new(None) };
+}
+
+impl KeyStatsSampler {
+    pub(crate) fn new() -> KeyStatsSampler {
+        KeyStatsSampler {
+            every: AtomicU32::new(0),
+            shared: Arc::new(Shared {
+                random: RandomState::new(),
+                reservoir: Mutex::new(Reservoir::default()),
+            }),
+        }
+    }
+
+    /// Samples one in every `every` accesses from now on, or none if `every`
+    /// is zero. The stats gathered so far are dropped.
+    pub(crate) fn set_every(&self, every: u32) {
+        self.every.store(every, Ordering::Relaxed);
+        let mut reservoir = self.shared.reservoir();
+        *reservoir = Reservoir {
+            resets: reservoir.resets + 1,
+            ..Reservoir::default()
+        };
+    }
+
+    /// Records an access to the key with the given hash, if sampled.
+    pub(crate) fn record(&self, key_hash: u64, hit: bool) {
+        let every = self.every.load(Ordering::Relaxed);
+        if every == 0 {
+            return;
+        }
+        PENDING.with_borrow_mut(|pending| {
+            let pending = match pending {
+                Some(pending) if Arc::ptr_eq(&pending.shared, &self.shared) => pending,
+                // The buffer of the sampler last used by the thread, if any,
+                // is flushed as it is dropped.
+                _ => pending.insert(Pending::new(self.shared.clone())),
+            };
+            let sampled = pending.seen.is_multiple_of(every);
+            pending.seen = pending.seen.wrapping_add(1);
+            if sampled {
+                pending.accesses.push((key_hash, hit));
+                if pending.accesses.len() >= FLUSH_EVERY {
+                    pending.flush();
+                }
+            }
+        });
+    }
+
+    /// Returns the stats of the tracked keys, most misses first.
+    ///
+    /// The accesses buffered by the current thread are flushed first. Those
+    /// buffered by other threads only show up once they flush.
+    pub(crate) fn stats(&self) -> Vec<KeyStats> {
+        PENDING.with_borrow_mut(|pending| {
+            if let Some(pending) = pending
+                .as_mut()
+                .filter(|pending| Arc::ptr_eq(&pending.shared, &self.shared))
+            {
+                pending.flush();
+            }
+        });
+        let mut stats = self.shared.reservoir().keys.clone();
+        stats.sort_unstable_by(|a, b| b.misses.cmp(&a.misses).then(a.key_hash.cmp(&b.key_hash)));
+        stats
+    }
+}
+
+impl Shared {
+    fn reservoir(&self) -> MutexGuard<'_, Reservoir> {
+        // The reservoir is always left coherent, so poisoning can be ignored.
+        self.reservoir.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Reservoir {
+    fn record(&mut self, random: &RandomState, key_hash: u64, hit: bool) {
+        self.sampled += 1;
+        let i = match self.positions.get(&key_hash) {
+            Some(&i) => i,
+            None if self.keys.len() < MAX_SAMPLED_KEYS => {
+                let i = self.keys.len();
+                self.keys.push(KeyStats {
+                    key_hash,
+                    hits: 0,
+                    misses: 0,
+                });
+                self.positions.insert(key_hash, i);
+                i
+            }
+            None => {
+                // Replace a tracked key with probability `MAX_SAMPLED_KEYS / n`
+                // for the `n`-th sampled access, as in reservoir sampling.
+                let n = self.sampled;
+                let i = (random.hash_one(n) % n) as usize;
+                if i >= MAX_SAMPLED_KEYS {
+                    return;
+                }
+                let evicted = self.keys[i].key_hash;
+                self.positions.remove(&evicted);
+                self.positions.insert(key_hash, i);
+                self.keys[i] = KeyStats {
+                    key_hash,
+                    hits: 0,
+                    misses: 0,
+                };
+                i
+            }
+        };
+        let stats = &mut self.keys[i];
+        if hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+    }
+}
+
+impl Pending {
+    fn new(shared: Arc<Shared>) -> Pending {
+        let resets = shared.reservoir().resets;
+        Pending {
+            shared,
+            resets,
+            seen: 0,
+            accesses: Vec::with_capacity(FLUSH_EVERY),
+        }
+    }
+
+    /// Moves the buffered accesses into the reservoir, unless it was reset
+    /// since they were buffered.
+    fn flush(&mut self) {
+        let mut reservoir = self.shared.reservoir();
+        if reservoir.resets == self.resets {
+            for (key_hash, hit) in self.accesses.drain(..) {
+                reservoir.record(&self.shared.random, key_hash, hit);
+            }
+        } else {
+            self.accesses.clear();
+            self.resets = reservoir.resets;
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_sampled_key_stats() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        assert!(c.sampled_key_stats().is_empty());
+
+        c.set_key_stats_sampling(1);
+        drop(c.read(&1).unwrap());
+        drop(c.read(&5).unwrap());
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        drop(c.read(&2).unwrap());
+        assert_eq!(
+            c.sampled_key_stats(),
+            [
+                KeyStats {
+                    key_hash: 1,
+                    hits: 1,
+                    misses: 1,
+                },
+                KeyStats {
+                    key_hash: 2,
+                    hits: 1,
+                    misses: 1,
+                },
+                KeyStats {
+                    key_hash: 5,
+                    hits: 0,
+                    misses: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reservoir_bounded() {
+        let s = KeyStatsSampler::new();
+        s.set_every(1);
+        for hash in 0..10 * MAX_SAMPLED_KEYS as u64 {
+            s.record(hash, false);
+        }
+        let stats = s.stats();
+        assert_eq!(stats.len(), MAX_SAMPLED_KEYS);
+        assert!(stats.iter().all(|key| key.misses == 1));
+    }
+
+    #[test]
+    fn test_flushed_across_threads() {
+        let s = KeyStatsSampler::new();
+        s.set_every(1);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..FLUSH_EVERY {
+                    s.record(7, false);
+                }
+            });
+        });
+        let stats = s.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].misses, FLUSH_EVERY as u64);
+    }
+}
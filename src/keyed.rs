@@ -0,0 +1,103 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    sync::{Mutex, MutexGuard, TryLockError},
+};
+
+use crate::slot_index;
+
+/// A fixed set of mutexes striped by key hash, for mutual exclusion per key
+/// without caching anything.
+///
+/// Keys map to stripes the same way they map to slots in a [`Cache`] with as
+/// many slots, so distinct keys may share (and contend over) a stripe.
+///
+/// [`Cache`]: crate::Cache
+pub struct KeyedLocks<K, H = RandomState> {
+    stripes: Box<[Mutex<()>]>,
+    hasher: H,
+    _key: PhantomData<fn(&K)>,
+}
+
+impl<K, H> KeyedLocks<K, H>
+where
+    K: Hash,
+    H: BuildHasher,
+{
+    /// Constructs a new set of locks.
+    pub fn new<const STRIPES: usize>() -> KeyedLocks<K, H>
+    where
+        H: Default,
+    {
+        Self::with_hasher::<STRIPES>(H::default())
+    }
+
+    /// Constructs a new set of locks which maps keys to stripes using the given
+    /// hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `STRIPES` is zero.
+    pub fn with_hasher<const STRIPES: usize>(hasher: H) -> KeyedLocks<K, H> {
+        assert!(STRIPES > 0, "stripe count must be non-zero");
+        KeyedLocks {
+            stripes: (0..STRIPES).map(|_| Mutex::new(())).collect(),
+            hasher,
+            _key: PhantomData,
+        }
+    }
+
+    /// Locks the stripe of the given key, blocking until it is available.
+    pub fn lock(&self, key: &K) -> KeyedGuard<'_> {
+        // The stripes guard no data, so poisoning can be ignored.
+        let guard = self.stripe(key).lock().unwrap_or_else(|e| e.into_inner());
+        KeyedGuard { _guard: guard }
+    }
+
+    /// Locks the stripe of the given key if it is available.
+    pub fn try_lock(&self, key: &K) -> Option<KeyedGuard<'_>> {
+        match self.stripe(key).try_lock() {
+            Ok(guard) => Some(KeyedGuard { _guard: guard }),
+            Err(TryLockError::Poisoned(err)) => Some(KeyedGuard {
+                _guard: err.into_inner(),
+            }),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    fn stripe(&self, key: &K) -> &Mutex<()> {
+        let hash = self.hasher.hash_one(key);
+        &self.stripes[slot_index(hash, self.stripes.len())]
+    }
+}
+
+/// Holds the stripe of a key locked until dropped.
+pub struct KeyedGuard<'a> {
+    _guard: MutexGuard<'a, ()>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::TestHashBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_keyed_locks() {
+        let locks = KeyedLocks::<u32, TestHashBuilder>::new::<4>();
+
+        let g1 = locks.lock(&1);
+        assert!(locks.try_lock(&2).is_some());
+        // `5` shares the stripe of `1`.
+        assert!(locks.try_lock(&5).is_none());
+        drop(g1);
+        assert!(locks.try_lock(&5).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "stripe count must be non-zero")]
+    fn test_zero_stripes() {
+        KeyedLocks::<u32, TestHashBuilder>::new::<0>();
+    }
+}
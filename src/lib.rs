@@ -1,14 +1,124 @@
 use std::{
     collections::hash_map::RandomState,
-    hash::{BuildHasher, Hash, Hasher},
+    hash::{BuildHasher, Hash},
+    mem::ManuallyDrop,
     ops::{Deref, DerefMut},
-    sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    ptr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        TryLockError,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use tracing::info;
+#[cfg(debug_assertions)]
+use std::sync::atomic::AtomicBool;
 
-use crate::write_batch::WriteBatch;
+use tracing::{info, warn};
 
+use crate::{
+    collisions::CollisionTracker,
+    guards::{GuardRegistry, GuardToken},
+    journal::EventJournal,
+    key_stats::KeyStatsSampler,
+    occupancy::Occupancy,
+    sampling::TraceSampler,
+    shed::LoadQueue,
+    write_batch::BatchConfig,
+};
+
+#[cfg(feature = "fxhash")]
+pub use crate::fx::{FxBuildHasher, FxCache, FxHasher};
+pub use crate::guards::GuardKind;
+#[cfg(feature = "debug-guards")]
+pub use crate::guards::{LastWriter, OutstandingGuard};
+pub use crate::{
+    builder::CacheBuilder,
+    collisions::CollisionStats,
+    comparing::{ComparingCache, ComparisonReport},
+    compose::{Composed, Loader, Validator},
+    decode::{DecodeFrom, DecodeStrategy, Encoded},
+    entry::{CacheEntry, ConflictedEntry, OccupiedEntry, VacantEntry},
+    error::{CacheError, ErrorKind, LoadError, Operation},
+    footprint::MemFootprint,
+    handle::{CacheHandle, HandleError},
+    hint::{LoadHint, LoadReason},
+    journal::{CacheEvent, EventOutcome},
+    key_stats::KeyStats,
+    keyed::{KeyedGuard, KeyedLocks},
+    mapped::{MappedReadRef, MappedWriteRef},
+    misuse::MisusePolicy,
+    multi::MultiKeyError,
+    owned::{OwnedReadRef, OwnedWriteRef},
+    presets::{ByTag, Memoize, Memoizer, ReadThrough, TagKeys, Tagged},
+    quiesce::QuiesceReport,
+    reader::CacheReader,
+    request::{RequestLoader, Requested},
+    shared::{Shared, SharedStrategy},
+    shed::Overloaded,
+    small::{AtomicValue, SmallValueCache},
+    ticket::LoadTicket,
+    timeout::TimeoutError,
+    upgrade::UpgradableReadRef,
+    validate::{StrategyReport, Validation},
+    write_batch::{BatchWriteError, DuplicateWrites, FlushMode, FlushPanic, WriteBatch},
+};
+
+/// Asserts the given cache invariant in debug builds, or unconditionally when
+/// the `paranoid` feature is enabled.
+macro_rules! paranoid_assert {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "paranoid") {
+            assert!($($arg)*);
+        } else {
+            debug_assert!($($arg)*);
+        }
+    };
+}
+
+mod builder;
+mod collisions;
+mod comparing;
+mod compose;
+mod decode;
+mod define;
+mod entry;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod footprint;
+#[cfg(feature = "fxhash")]
+mod fx;
+mod guards;
+mod handle;
+mod hint;
+mod journal;
+mod key_stats;
+mod keyed;
+mod local;
+mod mapped;
+mod misuse;
+#[cfg(any(test, feature = "model"))]
+pub mod model;
+mod multi;
+mod occupancy;
+mod owned;
+mod presets;
+mod quiesce;
+mod reader;
+mod request;
+mod sampling;
+mod shared;
+mod shed;
+mod small;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+mod ticket;
+mod timeout;
+mod upgrade;
+mod validate;
 mod write_batch;
 
 /// A cache strategy implementations. Provides information about the cache's key
@@ -22,9 +132,106 @@ pub trait CacheStrategy {
     /// Loads the value for the given key.
     fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err>;
 
-    /// Checks if the given key corresponds to the given value. If not (i.e,
-    /// `false` returned), one assumes a cache key conflict.
+    /// Checks whether the given value conflicts with the given key, i.e.,
+    /// whether it was loaded for some other key which maps to the same slot.
+    /// If so (i.e., `true` returned), the cache reloads the key.
+    ///
+    /// Values loaded for a key must never conflict with it; see
+    /// [`Cache::validate_strategy`] to check a strategy for that.
     fn match_kv(key: &Self::Key, val: &Self::Val) -> bool;
+
+    /// Checks the given value against the given key, telling a value loaded
+    /// for some other key from an outdated value of the key itself. The cache
+    /// serves the value only on [`Validation::Match`], and reloads the key
+    /// otherwise, as a refresh if [`Validation::Stale`] (see [`LoadReason`]).
+    ///
+    /// Defaults to [`Validation::Conflict`] if [`CacheStrategy::match_kv`]
+    /// returns `true`, and to [`Validation::Match`] otherwise.
+    fn validate(key: &Self::Key, val: &Self::Val) -> Validation {
+        if Self::match_kv(key, val) {
+            Validation::Conflict
+        } else {
+            Validation::Match
+        }
+    }
+
+    /// Like [`CacheStrategy::validate`], but may fail, e.g. for strategies
+    /// consulting external metadata. Operations which may fail return the
+    /// error as a [`LoadError`]; the others treat the value as missing.
+    /// Defaults to [`CacheStrategy::validate`].
+    fn try_validate(key: &Self::Key, val: &Self::Val) -> Result<Validation, Self::Err> {
+        Ok(Self::validate(key, val))
+    }
+
+    /// Loads the value for the given key, possibly reading other keys through
+    /// the given [`CacheHandle`] (see its documentation for the restrictions
+    /// which apply). The handle also tells why the key is being loaded (see
+    /// [`CacheHandle::hint`]). Defaults to [`CacheStrategy::load`].
+    fn load_with<H>(
+        &mut self,
+        key: &Self::Key,
+        cache: &CacheHandle<'_, Self, H>,
+    ) -> Result<Self::Val, Self::Err>
+    where
+        Self: Sized,
+        Self::Key: Hash,
+        H: BuildHasher,
+    {
+        let _ = cache;
+        self.load(key)
+    }
+
+    /// Classifies the given load error, so that callers (and the cache) can
+    /// tell transient failures from permanent ones. Defaults to
+    /// [`ErrorKind::Transient`].
+    fn classify_err(err: &Self::Err) -> ErrorKind {
+        let _ = err;
+        ErrorKind::Transient
+    }
+
+    /// Tells whether the given values are identical, so that the reloads of
+    /// stale values which produce identical ones can be told apart from those
+    /// which diverge (see [`CacheStrategy::on_divergence`]). Defaults to
+    /// `false`, i.e., every reload diverges.
+    fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+        let _ = (a, b);
+        false
+    }
+
+    /// Called after a resident value of the given key conflicted with it (or
+    /// predated [`Cache::bump_epoch`]), i.e., was stale, and was reloaded into
+    /// a different value (see [`CacheStrategy::same_val`]), with both the
+    /// stale and the fresh values. Reloads into identical values are only
+    /// counted, by [`Cache::identical_reloads`].
+    ///
+    /// Comparing them lets applications audit [`CacheStrategy::match_kv`] or
+    /// reconcile drift from the backing store. Does nothing by default.
+    ///
+    /// The hook runs while the key's slot is locked for write, so it must not
+    /// access the cache.
+    fn on_divergence(&mut self, key: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+        let _ = (key, stale, fresh);
+    }
+
+    /// Checks domain invariants of the given value of the given key (e.g.,
+    /// that a page's checksum matches its id), panicking if they don't hold.
+    /// Does nothing by default.
+    ///
+    /// In debug builds only, the hook runs after a value is loaded, and after
+    /// a [`WriteRef`] (or [`Cache::map_values`]) modified it. Since neither
+    /// keeps the key around, the latter are checked on the next lookup of
+    /// the key, unless the strategy is locked then (e.g., by a load reading
+    /// it through a [`CacheHandle`]), in which case on a later one. Like
+    /// [`CacheStrategy::on_divergence`], it must not access the cache.
+    fn debug_validate(&self, key: &Self::Key, val: &Self::Val) {
+        let _ = (key, val);
+    }
+
+    /// Returns the keys to be loaded eagerly when the cache is constructed.
+    /// Keys which collide replace each other, so the last one wins.
+    fn initial_keys(&self) -> Vec<Self::Key> {
+        Vec::new()
+    }
 }
 
 /// The cache over a given [`CacheStrategy`].
@@ -32,80 +239,1223 @@ pub struct Cache<S, H = RandomState>
 where
     S: CacheStrategy,
 {
-    entries: Box<[RwLock<Option<S::Val>>]>,
+    entries: Box<[Slot<S::Val>]>,
     strategy: Mutex<S>,
     hasher: H,
+    guards: GuardRegistry,
+    journal: EventJournal,
+    key_stats: KeyStatsSampler,
+    collisions: CollisionTracker,
+    occupancy: Occupancy,
+    flush_panic: Mutex<Option<FlushPanic>>,
+    id: u64,
+    epoch: AtomicU64,
+    sampler: TraceSampler,
+    load_queue: LoadQueue<S::Err>,
+    load_budget: Mutex<Option<Duration>>,
+    batch_config: BatchConfig,
+    identical_reloads: AtomicU64,
 }
 
 impl<S, H> Cache<S, H>
 where
     S: CacheStrategy,
     S::Key: Hash,
-    H: BuildHasher + Default,
+    H: BuildHasher,
 {
-    const EL: RwLock<Option<S::Val>> = RwLock::new(None);
-
     /// Constructs a new cache.
-    pub fn new<const CAPACITY: usize>(strategy: S) -> Cache<S, H> {
+    pub fn new<const CAPACITY: usize>(strategy: S) -> Cache<S, H>
+    where
+        H: Default,
+    {
+        Self::with_hasher::<CAPACITY>(strategy, H::default())
+    }
+
+    /// Constructs a new cache which maps keys to slots using the given hasher.
+    pub fn with_hasher<const CAPACITY: usize>(strategy: S, hasher: H) -> Cache<S, H> {
+        Self::with_capacity_and_hasher(strategy, CAPACITY, hasher)
+    }
+
+    /// Constructs a new cache with the given number of slots, which may be
+    /// decided at runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(strategy: S, capacity: usize) -> Cache<S, H>
+    where
+        H: Default,
+    {
+        Self::with_capacity_and_hasher(strategy, capacity, H::default())
+    }
+
+    /// Constructs a new cache with the given number of slots, which maps keys
+    /// to slots using the given hasher.
+    ///
+    /// Every constructor preloads the strategy's initial keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity_and_hasher(strategy: S, capacity: usize, hasher: H) -> Cache<S, H> {
+        let cache = Cache::unwarmed(strategy, capacity, hasher);
+        cache.warm_initial_keys();
+        cache
+    }
+
+    /// Constructs a new cache as [`Cache::with_capacity_and_hasher`] does, but
+    /// without preloading the strategy's initial keys, so that it may still be
+    /// configured before any value is loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub(crate) fn unwarmed(strategy: S, capacity: usize, hasher: H) -> Cache<S, H> {
+        assert!(capacity > 0, "cache capacity must be non-zero");
         Cache {
-            entries: Vec::from([Self::EL; CAPACITY]).into_boxed_slice(),
+            entries: (0..capacity).map(|_| Slot::new()).collect(),
             strategy: Mutex::new(strategy),
-            hasher: H::default(),
+            hasher,
+            guards: GuardRegistry::new(),
+            journal: EventJournal::new(),
+            key_stats: KeyStatsSampler::new(),
+            collisions: CollisionTracker::new(capacity),
+            occupancy: Occupancy::new(),
+            flush_panic: Mutex::new(None),
+            id: local::next_cache_id(),
+            epoch: AtomicU64::new(0),
+            sampler: TraceSampler::new(),
+            load_queue: LoadQueue::new(None),
+            load_budget: Mutex::new(None),
+            batch_config: BatchConfig::default(),
+            identical_reloads: AtomicU64::new(0),
         }
     }
 
-    /// Computes the index using the given key.
-    fn key(&self, key: &S::Key) -> &RwLock<Option<S::Val>> {
-        let mut h = self.hasher.build_hasher();
-        key.hash(&mut h);
-        let i = h.finish() as usize % self.entries.len();
-        unsafe { self.entries.get_unchecked(i) }
+    /// Preloads the strategy's initial keys.
+    pub(crate) fn warm_initial_keys(&self) {
+        let keys = self.strategy().initial_keys();
+        self.warm(keys);
+    }
+
+    /// Computes the hash of the given key.
+    fn hash(&self, key: &S::Key) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    /// Returns the number of slots.
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the number of slots holding a value. Concurrent loads and
+    /// invalidations may change it right after it is read.
+    pub fn occupied(&self) -> usize {
+        self.occupancy.occupied()
+    }
+
+    /// Returns the index of the slot to which keys with the given hash map.
+    ///
+    /// The mapping is stable: it is always `hash % capacity`, where `hash` is
+    /// the key's hash as computed by the cache's hasher, on every platform.
+    pub fn slot_for_hash(&self, hash: u64) -> usize {
+        slot_index(hash, self.entries.len())
+    }
+
+    /// Returns the slot at the given index.
+    fn slot_at(&self, i: usize) -> &Slot<S::Val> {
+        if cfg!(feature = "paranoid") {
+            &self.entries[i]
+        } else {
+            unsafe { self.entries.get_unchecked(i) }
+        }
     }
 
     /// Acquires the value by the given key, for read.
-    pub fn read(&self, key: &S::Key) -> Result<ReadRef<'_, S::Val>, S::Err> {
-        info!("acquiring read lock...");
-        let mut guard = self.key(key).read().unwrap();
+    pub fn read(&self, key: &S::Key) -> Result<ReadRef<'_, S::Val>, LoadError<S::Err>> {
+        self.read_at(key, self.hash(key))
+    }
 
-        // FIXME: This may deadlock in case of conflict, which MUST NOT happen.
-        if guard.is_none() || S::match_kv(key, guard.as_ref().unwrap()) {
+    /// Like [`Cache::read`], for a key whose hash was already computed.
+    fn read_at(&self, key: &S::Key, hash: u64) -> Result<ReadRef<'_, S::Val>, LoadError<S::Err>> {
+        let traced = self.sampler.sample();
+        if traced {
+            info!("acquiring read lock...");
+        }
+        let slot = self.slot_for_hash(hash);
+        let mut guard = self.slot_at(slot).read();
+        if self.check_miss(key, hash, slot, Operation::Read, &guard)? {
             // One needs to unlock (i.e., drop) the read guard to acquire the
             // write guard to perform the load. Otherwise, it'd deadlock.
             drop(guard);
 
-            self.load(key, &mut self.key(key).write().unwrap())?;
+            let mut write_guard = self.slot_at(slot).write();
+            // Some other thread may have taken the write lock in between and
+            // have already loaded the key (or replaced it by a conflicting one).
+            self.fill(key, hash, slot, Operation::Read, &mut write_guard)?;
+
+            // Downgrade instead of re-acquiring the read lock so that no other
+            // writer may replace the value before it is returned.
+            if traced {
+                info!("downgrading to read lock to return...");
+            }
+            guard = RwLockWriteGuard::downgrade(write_guard);
+        } else {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+        }
+
+        Ok(ReadRef::new(
+            guard,
+            self.guards.track(slot, GuardKind::Read),
+        ))
+    }
+
+    /// Like [`Cache::read`], but the returned guard may later be upgraded to a
+    /// write guard over the same value (see [`UpgradableReadRef::upgrade`]).
+    ///
+    /// Only one upgradable guard over a slot may be alive at a time, and it
+    /// keeps writers out of the slot until dropped, so this blocks while
+    /// another one is alive or the slot is written.
+    pub fn read_upgradable(
+        &self,
+        key: &S::Key,
+    ) -> Result<UpgradableReadRef<'_, S::Val>, LoadError<S::Err>> {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let token = self.slot_at(slot).upgrade_token();
+        let mut guard = self.slot_at(slot).read();
+        if self.check_miss(key, hash, slot, Operation::Read, &guard)? {
+            drop(guard);
+            // No writer may take the slot while the token is held, so the key
+            // is still missing.
+            let mut write_guard = self.slot_at(slot).write_with_token(&token);
+            self.load(key, hash, Operation::Read, &mut write_guard)?;
+            guard = RwLockWriteGuard::downgrade(write_guard);
+        } else {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+        }
+        let read = ReadRef::new(guard, self.guards.track(slot, GuardKind::Read));
+        Ok(UpgradableReadRef::new(
+            read,
+            token,
+            self.slot_at(slot),
+            slot,
+            &self.guards,
+        ))
+    }
+
+    /// Like [`Cache::read`], but returns `None` instead of blocking if the
+    /// key's slot is locked, so that callers may fall back to the backing
+    /// store. The key is still loaded on a miss if the slot is free.
+    pub fn try_read(&self, key: &S::Key) -> Result<Option<ReadRef<'_, S::Val>>, LoadError<S::Err>> {
+        self.try_read_at(key, self.hash(key))
+    }
+
+    /// Like [`Cache::try_read`], for a key whose hash was already computed.
+    fn try_read_at(
+        &self,
+        key: &S::Key,
+        hash: u64,
+    ) -> Result<Option<ReadRef<'_, S::Val>>, LoadError<S::Err>> {
+        let slot = self.slot_for_hash(hash);
+        let Some(guard) = self.slot_at(slot).try_read() else {
+            return Ok(None);
+        };
+        let guard = if self.check_miss(key, hash, slot, Operation::Read, &guard)? {
+            drop(guard);
+            let Some(mut write_guard) = self.slot_at(slot).try_write() else {
+                return Ok(None);
+            };
+            self.fill(key, hash, slot, Operation::Read, &mut write_guard)?;
+            RwLockWriteGuard::downgrade(write_guard)
+        } else {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+            guard
+        };
+        Ok(Some(ReadRef::new(
+            guard,
+            self.guards.track(slot, GuardKind::Read),
+        )))
+    }
+
+    /// Acquires the values by the given keys at once, for read, loading the
+    /// missing ones. The values are returned in the order of the keys.
+    ///
+    /// Slots are locked in ascending order, so that concurrent calls can't
+    /// deadlock one another. Fails with [`MultiKeyError::SameSlot`], before
+    /// locking anything, if two of the keys map to the same slot.
+    pub fn read_many(
+        &self,
+        keys: &[S::Key],
+    ) -> Result<Vec<ReadRef<'_, S::Val>>, MultiKeyError<S::Err>> {
+        // Each key is hashed once, so that it is locked in the slot its place
+        // in the lock order was computed for.
+        let hashes: Vec<u64> = keys.iter().map(|key| self.hash(key)).collect();
+        let slots: Vec<usize> = hashes
+            .iter()
+            .map(|&hash| self.slot_for_hash(hash))
+            .collect();
+        let order = multi::lock_order(&slots)?;
+        let mut refs: Vec<Option<ReadRef<'_, S::Val>>> = keys.iter().map(|_| None).collect();
+        for i in order {
+            refs[i] = Some(self.read_at(&keys[i], hashes[i])?);
+        }
+        Ok(refs.into_iter().map(Option::unwrap).collect())
+    }
 
-            info!("acquiring new read lock to return...");
-            guard = self.key(key).read().unwrap();
+    /// Acquires the values by the given keys at once, for write, loading the
+    /// missing ones. The values are returned in the order of the keys.
+    ///
+    /// As with [`Cache::read_many`], slots are locked in ascending order, and
+    /// keys mapping to the same slot fail with [`MultiKeyError::SameSlot`]
+    /// before anything is locked.
+    pub fn write_many(
+        &self,
+        keys: &[S::Key],
+    ) -> Result<Vec<WriteRef<'_, S::Val>>, MultiKeyError<S::Err>> {
+        // Each key is hashed once, so that it is locked in the slot its place
+        // in the lock order was computed for.
+        let hashes: Vec<u64> = keys.iter().map(|key| self.hash(key)).collect();
+        let slots: Vec<usize> = hashes
+            .iter()
+            .map(|&hash| self.slot_for_hash(hash))
+            .collect();
+        let order = multi::lock_order(&slots)?;
+        let mut refs: Vec<Option<WriteRef<'_, S::Val>>> = keys.iter().map(|_| None).collect();
+        for i in order {
+            refs[i] = Some(self.write_for_hash(&keys[i], hashes[i], Operation::Write)?);
         }
+        Ok(refs.into_iter().map(Option::unwrap).collect())
+    }
 
-        Ok(ReadRef(guard))
+    /// Sets for how long loads are expected to take at most, or clears the
+    /// budget if `None`. The budget isn't enforced, but handed to the strategy
+    /// along with every load (see [`LoadHint`]). Unset by default.
+    pub fn set_load_budget(&self, budget: Option<Duration>) {
+        *self
+            .load_budget
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = budget;
     }
 
     /// Acquires the value by the given key, for write.
-    pub fn write(&self, key: &S::Key) -> Result<WriteRef<'_, S::Val>, S::Err> {
-        info!("acquiring write lock...");
-        let mut guard = self.key(key).write().unwrap();
-        // FIXME: This may deadlock in case of conflict, which MUST NOT happen.
-        if guard.is_none() || S::match_kv(key, guard.as_ref().unwrap()) {
-            self.load(key, &mut guard)?;
+    pub fn write(&self, key: &S::Key) -> Result<WriteRef<'_, S::Val>, LoadError<S::Err>> {
+        self.write_for(key, Operation::Write)
+    }
+
+    /// Acquires the value by the given key, for write, on behalf of the given
+    /// operation.
+    pub(crate) fn write_for(
+        &self,
+        key: &S::Key,
+        op: Operation,
+    ) -> Result<WriteRef<'_, S::Val>, LoadError<S::Err>> {
+        self.write_for_hash(key, self.hash(key), op)
+    }
+
+    /// Like [`Cache::write_for`], for a key whose hash was already computed.
+    fn write_for_hash(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        op: Operation,
+    ) -> Result<WriteRef<'_, S::Val>, LoadError<S::Err>> {
+        if self.sampler.sample() {
+            info!("acquiring write lock...");
         }
-        Ok(WriteRef(guard))
+        let slot = self.slot_for_hash(hash);
+        let mut guard = self.slot_at(slot).write();
+        self.fill(key, hash, slot, op, &mut guard)?;
+        Ok(WriteRef::new(
+            guard,
+            self.guards.track(slot, GuardKind::Write),
+        ))
+    }
+
+    /// Like [`Cache::write`], but returns `None` instead of blocking if the
+    /// key's slot is locked. The key is still loaded on a miss.
+    pub fn try_write(
+        &self,
+        key: &S::Key,
+    ) -> Result<Option<WriteRef<'_, S::Val>>, LoadError<S::Err>> {
+        self.try_write_at(key, self.hash(key))
+    }
+
+    /// Like [`Cache::try_write`], for a key whose hash was already computed.
+    fn try_write_at(
+        &self,
+        key: &S::Key,
+        hash: u64,
+    ) -> Result<Option<WriteRef<'_, S::Val>>, LoadError<S::Err>> {
+        let slot = self.slot_for_hash(hash);
+        let Some(mut guard) = self.slot_at(slot).try_write() else {
+            return Ok(None);
+        };
+        self.fill(key, hash, slot, Operation::Write, &mut guard)?;
+        Ok(Some(WriteRef::new(
+            guard,
+            self.guards.track(slot, GuardKind::Write),
+        )))
+    }
+
+    /// Like [`Cache::read`], but fails with [`TimeoutError::TimedOut`] if the
+    /// key's slot stays locked for longer than the given timeout. Loads aren't
+    /// bounded by the timeout once the slot is acquired.
+    pub fn read_timeout(
+        &self,
+        key: &S::Key,
+        timeout: Duration,
+    ) -> Result<ReadRef<'_, S::Val>, TimeoutError<S::Err>> {
+        let deadline = Instant::now() + timeout;
+        let hash = self.hash(key);
+        loop {
+            if let Some(guard) = self.try_read_at(key, hash)? {
+                return Ok(guard);
+            }
+            wait_for_lock(deadline, timeout)?;
+        }
+    }
+
+    /// Like [`Cache::write`], but fails with [`TimeoutError::TimedOut`] if the
+    /// key's slot stays locked for longer than the given timeout. Loads aren't
+    /// bounded by the timeout once the slot is acquired.
+    pub fn write_timeout(
+        &self,
+        key: &S::Key,
+        timeout: Duration,
+    ) -> Result<WriteRef<'_, S::Val>, TimeoutError<S::Err>> {
+        let deadline = Instant::now() + timeout;
+        let hash = self.hash(key);
+        loop {
+            if let Some(guard) = self.try_write_at(key, hash)? {
+                return Ok(guard);
+            }
+            wait_for_lock(deadline, timeout)?;
+        }
+    }
+
+    /// Loads each of the given keys which isn't resident yet, returning how
+    /// many were loaded. Keys which fail to load are skipped. Keys which
+    /// collide replace each other, so the last one wins.
+    pub fn warm<I>(&self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = S::Key>,
+    {
+        let mut loaded = 0;
+        for key in keys {
+            let hash = self.hash(&key);
+            let slot = self.slot_for_hash(hash);
+            let mut guard = self.slot_at(slot).write();
+            if !self.is_miss(&key, &guard) {
+                self.record(Operation::Preload, hash, slot, EventOutcome::Hit);
+                continue;
+            }
+            // Failures are already traced by `store`, so they're just skipped.
+            if self
+                .load(&key, hash, Operation::Preload, &mut guard)
+                .is_ok()
+            {
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+
+    /// Returns a copy of the value by the given key, loading it if needed.
+    ///
+    /// Each thread remembers the last value it read this way, which is
+    /// returned again, without touching the slot's lock, for as long as the
+    /// same key is read and the slot isn't locked for write. This suits
+    /// threads reading one key repeatedly in tight loops; other access
+    /// patterns just pay for the copy. The remembered copy is kept alive until
+    /// the thread reads another value this way, even past the cache's drop.
+    pub fn read_local(&self, key: &S::Key) -> Result<Arc<S::Val>, LoadError<S::Err>>
+    where
+        S::Val: Clone + Send + Sync + 'static,
+    {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let generation = self.slot_at(slot).generation();
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        if let Some(val) = local::lookup::<S::Val>(self.id, slot, hash, generation, epoch) {
+            if matches!(S::try_validate(key, &val), Ok(Validation::Match)) {
+                return Ok(val);
+            }
+        }
+
+        let guard = self.read(key)?;
+        let val = Arc::new(S::Val::clone(&guard));
+        // No writer can bump the generation while the read guard is held.
+        let generation = self.slot_at(slot).generation();
+        let epoch = guard.guard.as_ref().map_or(epoch, |entry| entry.epoch);
+        local::remember(self.id, slot, hash, generation, epoch, val.clone());
+        Ok(val)
+    }
+
+    /// Acquires the value by the given key, for read, or returns `None` if
+    /// the key doesn't exist, i.e., if the strategy fails with
+    /// [`ErrorKind::NotFound`].
+    ///
+    /// The key's absence is remembered in its slot, so that later calls for
+    /// the same key return `None` without loading, until the slot is taken
+    /// over by another key. Other operations ignore remembered absences.
+    pub fn read_optional(
+        &self,
+        key: &S::Key,
+    ) -> Result<Option<ReadRef<'_, S::Val>>, LoadError<S::Err>> {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let guard = self.slot_at(slot).read();
+        if self.check_miss(key, hash, slot, Operation::Read, &guard)? {
+            drop(guard);
+        } else {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+            return Ok(Some(ReadRef::new(
+                guard,
+                self.guards.track(slot, GuardKind::Read),
+            )));
+        }
+
+        let mut write_guard = self.slot_at(slot).write();
+        if self.is_absent(hash, &write_guard) {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+            return Ok(None);
+        }
+        if self.check_miss(key, hash, slot, Operation::Read, &write_guard)? {
+            match self.load(key, hash, Operation::Read, &mut write_guard) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {
+                    self.replace_entry(
+                        &mut write_guard,
+                        Some(Entry {
+                            hash,
+                            val: None,
+                            epoch: self.epoch.load(Ordering::SeqCst),
+                            #[cfg(debug_assertions)]
+                            modified: AtomicBool::new(false),
+                        }),
+                    );
+                    return Ok(None);
+                }
+                Err(err) => return Err(err),
+            }
+        } else {
+            self.record(Operation::Read, hash, slot, EventOutcome::Hit);
+        }
+        Ok(Some(ReadRef::new(
+            RwLockWriteGuard::downgrade(write_guard),
+            self.guards.track(slot, GuardKind::Read),
+        )))
+    }
+
+    /// Acquires the value by the given key, for read, if it is resident.
+    /// Never loads; returns `None` on a miss or a conflicting slot instead.
+    pub fn peek(&self, key: &S::Key) -> Option<ReadRef<'_, S::Val>> {
+        let slot = self.slot_for_hash(self.hash(key));
+        let guard = self.slot_at(slot).read();
+        if self.is_miss(key, &guard) {
+            return None;
+        }
+        Some(ReadRef::new(
+            guard,
+            self.guards.track(slot, GuardKind::Read),
+        ))
+    }
+
+    /// Checks whether the value by the given key is resident. Never loads.
+    pub fn contains(&self, key: &S::Key) -> bool {
+        let slot = self.slot_for_hash(self.hash(key));
+        !self.is_miss(key, &self.slot_at(slot).read())
+    }
+
+    /// Like [`Cache::is_miss`], but fails with the strategy's error if the
+    /// strategy fails to validate the slot's value, rather than treating it as
+    /// a miss.
+    fn check_miss(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        slot: usize,
+        op: Operation,
+        opt: &Option<Entry<S::Val>>,
+    ) -> Result<bool, LoadError<S::Err>> {
+        let val = opt
+            .as_ref()
+            .filter(|entry| self.is_current(entry))
+            .and_then(|entry| entry.val.as_ref());
+        let Some(val) = val else {
+            return Ok(true);
+        };
+        match S::try_validate(key, val) {
+            Ok(Validation::Match) => {
+                self.debug_check(key, opt);
+                Ok(false)
+            }
+            Ok(_) => Ok(true),
+            Err(err) => {
+                warn!("failed to validate key with hash {hash:#018x} in slot {slot} on {op}");
+                self.record(op, hash, slot, EventOutcome::LoadFailed);
+                let kind = S::classify_err(&err);
+                Err(LoadError::new(err, kind, hash, slot, op))
+            }
+        }
+    }
+
+    /// Loads the given key into its write-locked slot, unless the slot holds it
+    /// already.
+    fn fill(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        slot: usize,
+        op: Operation,
+        opt: &mut Option<Entry<S::Val>>,
+    ) -> Result<(), LoadError<S::Err>> {
+        if self.check_miss(key, hash, slot, op, opt)? {
+            self.load(key, hash, op, opt)?;
+        } else {
+            self.record(op, hash, slot, EventOutcome::Hit);
+        }
+        Ok(())
+    }
+
+    /// Checks whether the given slot contents don't hold the given key. Values
+    /// which the strategy fails to validate are treated as missing.
+    fn is_miss(&self, key: &S::Key, opt: &Option<Entry<S::Val>>) -> bool {
+        let miss = opt
+            .as_ref()
+            .filter(|entry| self.is_current(entry))
+            .and_then(|entry| entry.val.as_ref())
+            .is_none_or(|val| !matches!(S::try_validate(key, val), Ok(Validation::Match)));
+        if !miss {
+            self.debug_check(key, opt);
+        }
+        miss
+    }
+
+    /// Runs [`CacheStrategy::debug_validate`] over the value of the given key
+    /// in the given slot contents, if a writer modified it since it was last
+    /// checked. Skipped while the strategy is locked, leaving the value to be
+    /// checked on a later lookup.
+    #[cfg(debug_assertions)]
+    fn debug_check(&self, key: &S::Key, opt: &Option<Entry<S::Val>>) {
+        let Some(entry) = opt
+            .as_ref()
+            .filter(|entry| entry.modified.load(Ordering::Relaxed))
+        else {
+            return;
+        };
+        let strategy = match self.strategy.try_lock() {
+            Ok(strategy) => strategy,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => return,
+        };
+        if entry.modified.swap(false, Ordering::Relaxed) {
+            strategy.debug_validate(key, Entry::val(opt));
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn debug_check(&self, _: &S::Key, _: &Option<Entry<S::Val>>) {}
+
+    /// Checks whether the given slot contents record the absence of the key
+    /// with the given hash.
+    fn is_absent(&self, hash: u64, opt: &Option<Entry<S::Val>>) -> bool {
+        opt.as_ref().is_some_and(|entry| {
+            entry.hash == hash && entry.val.is_none() && self.is_current(entry)
+        })
+    }
+
+    /// Checks whether the given entry was stored in the current epoch, i.e.,
+    /// hasn't been invalidated by [`Cache::bump_epoch`] since.
+    fn is_current(&self, entry: &Entry<S::Val>) -> bool {
+        entry.epoch == self.epoch.load(Ordering::SeqCst)
     }
 
     /// Loads the entry for the given key.
-    fn load(&self, key: &S::Key, opt: &mut Option<S::Val>) -> Result<(), S::Err> {
-        info!("storing new `load result`...");
-        opt.replace({
-            let mut load_guard = self.strategy.lock().unwrap();
-            load_guard.load(key)?
-        });
+    fn load(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        op: Operation,
+        opt: &mut Option<Entry<S::Val>>,
+    ) -> Result<(), LoadError<S::Err>> {
+        let stale = self.is_stale(key, hash, opt);
+        let slot = self.slot_for_hash(hash);
+        let shed = |err| {
+            warn!("shed load of key with hash {hash:#018x} into slot {slot} on {op}");
+            self.record(op, hash, slot, EventOutcome::LoadFailed);
+            LoadError::new(err, ErrorKind::Overloaded, hash, slot, op)
+        };
+        let _pending = self.load_queue.enter().map_err(shed)?;
+        let mut load_guard = self.load_queue.lock(&self.strategy).map_err(shed)?;
+        let displaced = self.store(key, hash, op, opt, move || {
+            let hint = LoadHint {
+                budget: *self
+                    .load_budget
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+                reason: LoadReason::of(op, stale),
+            };
+            let handle = CacheHandle::new(self, slot, hint);
+            load_guard.load_with(key, &handle)
+        })?;
+        if cfg!(debug_assertions) {
+            self.strategy().debug_validate(key, Entry::val(opt));
+        }
+        if let Some(stale) = displaced.filter(|_| stale) {
+            self.report_reload(&mut self.strategy(), key, &stale, Entry::val(opt));
+        }
         Ok(())
     }
 
+    /// Checks whether the given slot holds a stale value of the given key,
+    /// i.e., one which a load of the key reloads.
+    fn is_stale(&self, key: &S::Key, hash: u64, opt: &Option<Entry<S::Val>>) -> bool {
+        opt.as_ref().is_some_and(|entry| {
+            entry.val.as_ref().is_some_and(|val| {
+                entry.hash == hash || matches!(S::try_validate(key, val), Ok(Validation::Stale))
+            })
+        })
+    }
+
+    /// Reports the reload of a stale value of the given key, counting it if
+    /// the fresh value is identical, or handing both to the strategy if they
+    /// diverge.
+    fn report_reload(&self, strategy: &mut S, key: &S::Key, stale: &S::Val, fresh: &S::Val) {
+        if S::same_val(stale, fresh) {
+            self.identical_reloads.fetch_add(1, Ordering::Relaxed);
+        } else {
+            strategy.on_divergence(key, stale, fresh);
+        }
+    }
+
+    /// Stores the value produced by `f` as the entry for the given key,
+    /// returning the value it displaced, if any.
+    fn store<F>(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        op: Operation,
+        opt: &mut Option<Entry<S::Val>>,
+        f: F,
+    ) -> Result<Option<S::Val>, LoadError<S::Err>>
+    where
+        F: FnOnce() -> Result<S::Val, S::Err>,
+    {
+        info!("storing new `load result`...");
+        match f() {
+            Ok(val) => Ok(self.store_val(key, hash, op, opt, val)),
+            Err(err) => {
+                let slot = self.slot_for_hash(hash);
+                warn!("failed to load key with hash {hash:#018x} into slot {slot} on {op}");
+                self.record(op, hash, slot, EventOutcome::LoadFailed);
+                let kind = S::classify_err(&err);
+                Err(LoadError::new(err, kind, hash, slot, op))
+            }
+        }
+    }
+
+    /// Stores the given value as the entry for the given key, returning the
+    /// value it displaced, if any.
+    fn store_val(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        op: Operation,
+        opt: &mut Option<Entry<S::Val>>,
+        val: S::Val,
+    ) -> Option<S::Val> {
+        let slot = self.slot_for_hash(hash);
+        paranoid_assert!(
+            !matches!(S::try_validate(key, &val), Ok(Validation::Conflict)),
+            "stored a value which conflicts with its own key"
+        );
+        self.guards.record_writer(slot);
+        let outcome = if opt.is_some() {
+            EventOutcome::Replaced
+        } else {
+            EventOutcome::Loaded
+        };
+        self.record(op, hash, slot, outcome);
+        let previous = opt
+            .as_ref()
+            .filter(|entry| entry.val.is_some() && self.is_current(entry));
+        self.collisions
+            .record_load(slot, hash, previous.map(|entry| entry.hash));
+        let displaced = self.replace_entry(
+            opt,
+            Some(Entry {
+                hash,
+                val: Some(val),
+                epoch: self.epoch.load(Ordering::SeqCst),
+                #[cfg(debug_assertions)]
+                modified: AtomicBool::new(false),
+            }),
+        );
+        displaced.and_then(|entry| entry.val)
+    }
+
+    /// Locks the strategy. Poisoning is ignored, so that a strategy which
+    /// panicked while loading is trusted to be left usable; the slot it was
+    /// loading into is left untouched.
+    fn strategy(&self) -> MutexGuard<'_, S> {
+        self.strategy.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records the outcome of an operation over a slot to the event journal
+    /// and the key stats sampler.
+    fn record(&self, op: Operation, hash: u64, slot: usize, outcome: EventOutcome) {
+        self.journal.record(op, hash, slot, outcome);
+        self.key_stats.record(hash, outcome == EventOutcome::Hit);
+    }
+
+    /// Replaces the given slot contents, keeping the occupancy count in sync.
+    fn replace_entry(
+        &self,
+        opt: &mut Option<Entry<S::Val>>,
+        new: Option<Entry<S::Val>>,
+    ) -> Option<Entry<S::Val>> {
+        let has_val = Entry::has_val(&new);
+        let old = std::mem::replace(opt, new);
+        self.occupancy.update(Entry::has_val(&old), has_val);
+        old
+    }
+
+    /// Places the given value in the slot of the given key, without loading
+    /// it through the strategy. Returns the value it displaced, which may have
+    /// belonged to another (colliding) key.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn insert(&self, key: &S::Key, val: S::Val) -> Option<S::Val> {
+        let hash = self.hash(key);
+        let mut guard = self.slot_at(self.slot_for_hash(hash)).write();
+        self.store_val(key, hash, Operation::Insert, &mut guard, val)
+    }
+
+    /// Like [`Cache::insert`], but also tells whether the displaced value was
+    /// the key's own (current) value or that of a colliding key, e.g. to
+    /// restore values produced externally from a snapshot.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn replace(&self, key: &S::Key, val: S::Val) -> Option<Replaced<S::Val>> {
+        let hash = self.hash(key);
+        let mut guard = self.slot_at(self.slot_for_hash(hash)).write();
+        let matched = !self.is_miss(key, &guard);
+        let displaced = self.store_val(key, hash, Operation::Insert, &mut guard, val);
+        displaced.map(|val| Replaced { val, matched })
+    }
+
+    /// Acquires the value by the given key, for read, computing it with the
+    /// given function instead of the strategy if the key isn't resident. The
+    /// function's errors are returned as is.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn get_or_insert_with<F, E>(&self, key: &S::Key, f: F) -> Result<ReadRef<'_, S::Val>, E>
+    where
+        F: FnOnce() -> Result<S::Val, E>,
+    {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let guard = self.slot_at(slot).read();
+        if !self.is_miss(key, &guard) {
+            self.record(Operation::Insert, hash, slot, EventOutcome::Hit);
+            return Ok(ReadRef::new(
+                guard,
+                self.guards.track(slot, GuardKind::Read),
+            ));
+        }
+        drop(guard);
+
+        let mut write_guard = self.slot_at(slot).write();
+        if self.is_miss(key, &write_guard) {
+            let val = f().inspect_err(|_| {
+                self.record(Operation::Insert, hash, slot, EventOutcome::LoadFailed);
+            })?;
+            self.store_val(key, hash, Operation::Insert, &mut write_guard, val);
+        } else {
+            self.record(Operation::Insert, hash, slot, EventOutcome::Hit);
+        }
+        Ok(ReadRef::new(
+            RwLockWriteGuard::downgrade(write_guard),
+            self.guards.track(slot, GuardKind::Read),
+        ))
+    }
+
+    /// Acquires the value by the given key, for write, computing it with the
+    /// given function instead of the strategy if the key isn't resident, e.g.
+    /// to overwrite it without paying for a load.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    pub fn write_or_insert_with<F>(&self, key: &S::Key, f: F) -> WriteRef<'_, S::Val>
+    where
+        F: FnOnce() -> S::Val,
+    {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let mut guard = self.slot_at(slot).write();
+        if self.is_miss(key, &guard) {
+            self.store_val(key, hash, Operation::Insert, &mut guard, f());
+        } else {
+            self.record(Operation::Insert, hash, slot, EventOutcome::Hit);
+        }
+        WriteRef::new(guard, self.guards.track(slot, GuardKind::Write))
+    }
+
+    /// Locks the slot of the given key for write, returning a view telling
+    /// whether it holds the key's value, no value, or a conflicting one, along
+    /// with the operations which apply to each case.
+    pub fn entry<'a>(&'a self, key: &'a S::Key) -> CacheEntry<'a, S, H> {
+        CacheEntry::new(self, key)
+    }
+
+    /// Reserves the slot of the given key for a load performed outside of the
+    /// cache, e.g. by an external system, or returns `None` if the key is
+    /// already resident.
+    ///
+    /// The slot stays locked until the returned ticket is passed to
+    /// [`Cache::complete_load`] or [`Cache::fail_load`] (or dropped), so
+    /// concurrent accesses to it wait just as they would for a strategy load.
+    pub fn begin_load<'a>(&'a self, key: &'a S::Key) -> Option<LoadTicket<'a, S, H>> {
+        let hash = self.hash(key);
+        let slot = self.slot_for_hash(hash);
+        let guard = self.slot_at(slot).write();
+        if !self.is_miss(key, &guard) {
+            self.record(Operation::ExternalLoad, hash, slot, EventOutcome::Hit);
+            return None;
+        }
+        Some(LoadTicket {
+            cache: self,
+            key,
+            hash,
+            guard,
+            _token: self.guards.track(slot, GuardKind::Write),
+        })
+    }
+
+    /// Stores the externally loaded value for the ticket's key and returns
+    /// it, for read.
+    ///
+    /// The value must not conflict with the key (see
+    /// [`CacheStrategy::match_kv`]).
+    ///
+    /// Passing a ticket issued by another cache is reported as misuse (see
+    /// [`MisusePolicy`]); the load is then completed on that cache.
+    pub fn complete_load<'a>(
+        &'a self,
+        ticket: LoadTicket<'a, S, H>,
+        val: S::Val,
+    ) -> ReadRef<'a, S::Val> {
+        if !std::ptr::eq(ticket.cache, self) {
+            misuse::report(format_args!("load ticket issued by another cache"));
+            return ticket.cache.complete_load(ticket, val);
+        }
+        let LoadTicket {
+            key,
+            hash,
+            mut guard,
+            ..
+        } = ticket;
+        self.store_val(key, hash, Operation::ExternalLoad, &mut guard, val);
+        ReadRef::new(
+            RwLockWriteGuard::downgrade(guard),
+            self.guards.track(self.slot_for_hash(hash), GuardKind::Read),
+        )
+    }
+
+    /// Abandons the ticket's load, which failed with the given error, leaving
+    /// the slot as it was. Returns the error along with its context.
+    ///
+    /// Passing a ticket issued by another cache is reported as misuse (see
+    /// [`MisusePolicy`]); the load is then failed on that cache.
+    pub fn fail_load(&self, ticket: LoadTicket<'_, S, H>, err: S::Err) -> LoadError<S::Err> {
+        if !std::ptr::eq(ticket.cache, self) {
+            misuse::report(format_args!("load ticket issued by another cache"));
+            return ticket.cache.fail_load(ticket, err);
+        }
+        let LoadTicket {
+            key,
+            hash,
+            mut guard,
+            ..
+        } = ticket;
+        match self.store(key, hash, Operation::ExternalLoad, &mut guard, || Err(err)) {
+            Ok(_) => unreachable!("storing an error can't succeed"),
+            Err(err) => err,
+        }
+    }
+
     /// Batch edit.
-    pub fn write_batch(&self) -> WriteBatch<'_, S, H> {
-        WriteBatch::new(self)
+    pub fn write_batch(&self) -> WriteBatch<'_, S, H>
+    where
+        H: Clone,
+    {
+        self.write_batch_with_capacity(8)
+    }
+
+    /// Like [`Cache::write_batch`], but has room for `capacity` entries up
+    /// front, which avoids growing the batch during large ingestions.
+    pub fn write_batch_with_capacity(&self, capacity: usize) -> WriteBatch<'_, S, H>
+    where
+        H: Clone,
+    {
+        WriteBatch::new(self, capacity)
+    }
+
+    /// Returns the record of the last batch flush function which panicked, if
+    /// any, clearing it.
+    pub fn take_flush_panic(&self) -> Option<FlushPanic> {
+        self.flush_panic
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    pub(crate) fn record_flush_panic(&self, record: FlushPanic) {
+        *self.flush_panic.lock().unwrap_or_else(|e| e.into_inner()) = Some(record);
+    }
+
+    /// Returns a read-only view over the cache.
+    pub fn reader(&self) -> CacheReader<'_, S, H> {
+        CacheReader::new(self)
+    }
+
+    /// Removes the value by the given key, so that the next access reloads it,
+    /// and returns it if it was resident. A remembered absence of the key is
+    /// dropped as well.
+    pub fn invalidate(&self, key: &S::Key) -> Option<S::Val> {
+        let hash = self.hash(key);
+        let mut guard = self.slot_at(self.slot_for_hash(hash)).write();
+        if !self.is_miss(key, &guard) || self.is_absent(hash, &guard) {
+            self.replace_entry(&mut guard, None)
+                .and_then(|entry| entry.val)
+        } else {
+            None
+        }
+    }
+
+    /// Removes the values by the given keys, returning how many of them were
+    /// resident. Remembered absences of the keys are dropped as well.
+    ///
+    /// Keys are grouped by slot, so that each slot's write lock is only taken
+    /// once.
+    pub fn invalidate_many(&self, keys: &[S::Key]) -> usize {
+        let mut by_slot: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                let hash = self.hash(key);
+                (self.slot_for_hash(hash), hash, key)
+            })
+            .collect();
+        by_slot.sort_unstable_by_key(|&(slot, ..)| slot);
+
+        let mut invalidated = 0;
+        for group in by_slot.chunk_by(|a, b| a.0 == b.0) {
+            let mut guard = self.slot_at(group[0].0).write();
+            if group.iter().any(|&(_, _, key)| !self.is_miss(key, &guard)) {
+                self.replace_entry(&mut guard, None);
+                invalidated += 1;
+            } else if group
+                .iter()
+                .any(|&(_, hash, _)| self.is_absent(hash, &guard))
+            {
+                self.replace_entry(&mut guard, None);
+            }
+        }
+        invalidated
+    }
+
+    /// Removes every value, along with remembered absences, so that every key
+    /// is reloaded on its next access. Each slot's write lock is taken in
+    /// turn, so concurrent accesses may still observe the cache partially
+    /// cleared.
+    pub fn clear(&self) {
+        for entry in self.entries.iter() {
+            let mut guard = entry.write();
+            if guard.is_some() {
+                self.replace_entry(&mut guard, None);
+            }
+        }
+    }
+
+    /// Removes every resident value for which the given predicate returns
+    /// `false`, returning how many were removed.
+    ///
+    /// Slots are visited in order, each under its write lock, which is only
+    /// held while the predicate runs over the slot's value.
+    pub fn retain<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(&S::Val) -> bool,
+    {
+        let mut removed = 0;
+        for entry in self.entries.iter() {
+            let mut guard = entry.write();
+            let current = guard.as_ref().is_some_and(|entry| self.is_current(entry));
+            if current && Entry::has_val(&guard) && !f(Entry::val(&guard)) {
+                self.replace_entry(&mut guard, None);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Invalidates every resident value at once, so that every key is reloaded
+    /// on its next access, without visiting the slots as [`Cache::clear`]
+    /// does. Remembered absences are dropped as well.
+    ///
+    /// Invalidated values are only released as their slots get reloaded, so
+    /// they still count as occupied (see [`Cache::occupied`]) until then.
+    pub fn bump_epoch(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Loads each of the given sample keys with the strategy and checks that
+    /// the loaded values don't conflict with their own keys, which catches
+    /// e.g. an inverted [`CacheStrategy::match_kv`]. Meant to be run once at
+    /// startup.
+    ///
+    /// The loaded values are discarded rather than stored in the cache.
+    pub fn validate_strategy(&self, sample_keys: &[S::Key]) -> StrategyReport<S::Err> {
+        let mut report = StrategyReport {
+            loaded: 0,
+            conflicting: Vec::new(),
+            failed: Vec::new(),
+        };
+        let mut strategy = self.strategy();
+        for (i, key) in sample_keys.iter().enumerate() {
+            let validation = strategy
+                .load(key)
+                .and_then(|val| S::try_validate(key, &val));
+            match validation {
+                Ok(validation) => {
+                    report.loaded += 1;
+                    if validation != Validation::Match {
+                        report.conflicting.push(i);
+                    }
+                }
+                Err(err) => {
+                    let hash = self.hash(key);
+                    let kind = S::classify_err(&err);
+                    let err = LoadError::new(
+                        err,
+                        kind,
+                        hash,
+                        self.slot_for_hash(hash),
+                        Operation::Validate,
+                    );
+                    report.failed.push((i, err));
+                }
+            }
+        }
+        report
+    }
+
+    /// Calls the given function with every resident value, along with the hash
+    /// of the key it was loaded for, returning how many values were visited.
+    ///
+    /// Slots are visited in order, each under its read lock, which is only
+    /// held while the function runs over the slot's value.
+    pub fn for_each_resident<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(u64, &S::Val),
+    {
+        let mut visited = 0;
+        for i in 0..self.entries.len() {
+            if let Some(Entry {
+                hash,
+                val: Some(val),
+                ..
+            }) = self
+                .slot_at(i)
+                .read()
+                .as_ref()
+                .filter(|entry| self.is_current(entry))
+            {
+                f(*hash, val);
+                visited += 1;
+            }
+        }
+        visited
+    }
+
+    /// Applies the given function to every resident value, along with the hash
+    /// of the key it was loaded for, returning how many values were visited.
+    ///
+    /// Slots are visited in order, each under its write lock, which is only
+    /// held while the function runs over the slot's value.
+    pub fn map_values<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(u64, &mut S::Val),
+    {
+        let mut visited = 0;
+        for i in 0..self.entries.len() {
+            let mut guard = self.slot_at(i).write();
+            let Some(entry) = guard.as_mut().filter(|entry| self.is_current(entry)) else {
+                continue;
+            };
+            if let Some(val) = entry.val.as_mut() {
+                f(entry.hash, val);
+                entry.mark_modified();
+                visited += 1;
+            }
+        }
+        visited
+    }
+
+    /// Keeps the last `capacity` cache events (i.e., the decision taken by each
+    /// operation over its slot) in memory, to be inspected with
+    /// [`Cache::recent_events`]. A zero `capacity` disables the journal, which
+    /// is the default.
+    pub fn set_event_journal_capacity(&self, capacity: usize) {
+        self.journal.set_capacity(capacity);
+    }
+
+    /// Traces the lock acquisition of only one in every `every` operations (as
+    /// counted by each thread), or of none if `every` is zero. Loads and load
+    /// failures are always traced. Defaults to tracing every operation.
+    pub fn set_trace_sampling(&self, every: u32) {
+        self.sampler.set_every(every);
+    }
+
+    /// Returns how many stale values were reloaded into identical ones (see
+    /// [`CacheStrategy::same_val`]).
+    pub fn identical_reloads(&self) -> u64 {
+        self.identical_reloads.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many loads were caused by slot collisions, along with the
+    /// (up to) `top` most frequently colliding pairs of key hashes.
+    pub fn collision_stats(&self, top: usize) -> CollisionStats {
+        self.collisions.stats(top)
+    }
+
+    /// Tracks the hits and misses of a sample of the accessed keys, sampling
+    /// one in every `every` accesses, or none if `every` is zero (the
+    /// default). The stats gathered so far are dropped.
+    ///
+    /// Only a bounded number of keys are tracked at once, picked by reservoir
+    /// sampling, so that frequently accessed keys are the likeliest to show up
+    /// in [`Cache::sampled_key_stats`].
+    pub fn set_key_stats_sampling(&self, every: u32) {
+        self.key_stats.set_every(every);
+    }
+
+    /// Returns the stats of the currently sampled keys, most misses first.
+    ///
+    /// Each thread buffers its sampled accesses, flushing them in batches, so
+    /// the latest accesses of threads other than the current one may not be
+    /// counted yet.
+    pub fn sampled_key_stats(&self) -> Vec<KeyStats> {
+        self.key_stats.stats()
+    }
+
+    /// Returns the events kept by the event journal, oldest first.
+    pub fn recent_events(&self) -> Vec<CacheEvent> {
+        self.journal.events()
     }
 
     /// Returns a copy of the current strategy.
@@ -113,46 +1463,418 @@ where
     where
         S: Clone,
     {
-        self.strategy.lock().unwrap().clone()
+        self.strategy().clone()
     }
 
     /// Returns the inner strategy.
     pub fn into_strategy(self) -> S {
-        self.strategy.into_inner().unwrap()
+        self.strategy
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Panics if any guard over the cache's entries is still alive.
+    ///
+    /// With the `debug-guards` feature, the panic message lists every
+    /// outstanding guard along with the backtrace of where it was created.
+    /// Otherwise, it only lists the slots which are still locked.
+    pub fn assert_no_outstanding_guards(&self) {
+        #[cfg(feature = "debug-guards")]
+        {
+            let guards = self.guards.outstanding();
+            assert!(guards.is_empty(), "outstanding guards: {guards:#?}");
+        }
+        #[cfg(not(feature = "debug-guards"))]
+        {
+            let locked: Vec<_> = (0..self.entries.len())
+                .filter(|&i| self.slot_at(i).probe().is_none())
+                .collect();
+            assert!(
+                locked.is_empty(),
+                "outstanding guards over slots {locked:?}"
+            );
+        }
+    }
+
+    /// Returns every guard over the cache's entries which is still alive,
+    /// oldest first.
+    #[cfg(feature = "debug-guards")]
+    pub fn outstanding_guards(&self) -> Vec<OutstandingGuard> {
+        self.guards.outstanding()
+    }
+
+    /// Returns the thread which last stored a value in the given slot or
+    /// acquired it for write, if any did, e.g. to tell which of the keys
+    /// colliding over a slot keeps evicting the others.
+    #[cfg(feature = "debug-guards")]
+    pub fn last_writer(&self, slot: usize) -> Option<LastWriter> {
+        self.guards.last_writer(slot)
+    }
+
+    /// Logs a warning, including its creation backtrace, for every guard which
+    /// has been alive for longer than `threshold`, returning how many there
+    /// are. Meant to be called periodically, e.g., from a maintenance thread.
+    #[cfg(feature = "debug-guards")]
+    pub fn warn_old_guards(&self, threshold: std::time::Duration) -> usize {
+        let old: Vec<_> = self
+            .guards
+            .outstanding()
+            .into_iter()
+            .filter(|g| g.age() > threshold)
+            .collect();
+        for guard in &old {
+            warn!("possibly leaked guard: {guard:?}");
+        }
+        old.len()
+    }
+
+    /// Sets for how long write guards are expected to be held, or clears the
+    /// lease if `None`. Write guards which outlive it can't be revoked, but
+    /// they are reported by [`Cache::expired_write_leases`] while alive, and
+    /// logged as warnings, including their creation backtrace, once dropped.
+    #[cfg(feature = "debug-guards")]
+    pub fn set_write_lease(&self, lease: Option<Duration>) {
+        self.guards.set_write_lease(lease);
+    }
+
+    /// Returns the write guards which are still alive past the write lease,
+    /// oldest first.
+    #[cfg(feature = "debug-guards")]
+    pub fn expired_write_leases(&self) -> Vec<OutstandingGuard> {
+        self.guards.expired_write_leases()
+    }
+
+    /// Waits until no slot is write-locked and the strategy isn't loading, or
+    /// until the timeout elapses, whichever comes first.
+    ///
+    /// Read guards don't hold up quiescence. The slots are polled one by one,
+    /// so a slot may be locked again right after it was observed free; callers
+    /// which need the cache to stay quiescent must stop issuing operations
+    /// beforehand.
+    pub fn quiesce(&self, timeout: Duration) -> QuiesceReport {
+        let start = Instant::now();
+        loop {
+            let busy_slots: Vec<_> = (0..self.entries.len())
+                .filter(|&i| self.slot_at(i).try_read().is_none())
+                .collect();
+            let loading = matches!(self.strategy.try_lock(), Err(TryLockError::WouldBlock));
+            let waited = start.elapsed();
+            if (busy_slots.is_empty() && !loading) || waited >= timeout {
+                return QuiesceReport {
+                    busy_slots,
+                    loading,
+                    waited,
+                };
+            }
+            thread::sleep(QUIESCE_POLL_INTERVAL);
+        }
+    }
+
+    /// Checks the cache's internal invariants, panicking if any is violated.
+    ///
+    /// This is meant for tests and fuzzing. It must only be called while no
+    /// guards or batches over the cache are alive, since every slot and the
+    /// strategy are then expected to be unlocked and not poisoned. Every entry
+    /// must also sit in the slot its hash maps to, and the occupancy count must
+    /// match the slots actually holding values.
+    pub fn check_invariants(&self) {
+        match self.strategy.try_lock() {
+            Ok(_) => {}
+            Err(TryLockError::WouldBlock) => panic!("strategy is still locked"),
+            Err(TryLockError::Poisoned(_)) => panic!("strategy is poisoned"),
+        }
+        let mut resident = 0;
+        for (i, entry) in self.entries.iter().enumerate() {
+            let Some(guard) = entry.probe() else {
+                panic!("slot {i} is still locked");
+            };
+            if entry.lock.is_poisoned() {
+                panic!("slot {i} is poisoned");
+            }
+            if let Some(entry) = &*guard {
+                let slot = self.slot_for_hash(entry.hash);
+                assert_eq!(slot, i, "slot {i} holds a key of slot {slot}");
+            }
+            resident += usize::from(Entry::has_val(&guard));
+        }
+        let occupied = self.occupancy.occupied();
+        assert_eq!(
+            occupied, resident,
+            "{occupied} slots counted as occupied, but {resident} hold values"
+        );
+    }
+}
+
+/// Maps a hash to one of `len` slots. The hash is reduced as a `u64`, so that
+/// the mapping doesn't depend on the platform's pointer width.
+pub(crate) fn slot_index(hash: u64, len: usize) -> usize {
+    (hash % len as u64) as usize
+}
+
+/// Sleeps before polling a locked slot again, unless the deadline has passed.
+fn wait_for_lock<E>(deadline: Instant, timeout: Duration) -> Result<(), TimeoutError<E>> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(TimeoutError::TimedOut { waited: timeout });
+    }
+    thread::sleep(remaining.min(LOCK_POLL_INTERVAL));
+    Ok(())
+}
+
+/// How often [`Cache::read_timeout`] and [`Cache::write_timeout`] poll a
+/// locked slot.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// How often [`Cache::quiesce`] polls the slots.
+const QUIESCE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A resident value, along with the hash of the key it was loaded for. A
+/// missing value records that the key is known not to exist.
+struct Entry<V> {
+    hash: u64,
+    val: Option<V>,
+    /// The cache's epoch when the entry was stored. Entries of past epochs are
+    /// treated as missing.
+    epoch: u64,
+    /// Whether a writer modified the value since the strategy last checked it
+    /// with [`CacheStrategy::debug_validate`].
+    #[cfg(debug_assertions)]
+    modified: AtomicBool,
+}
+
+impl<V> Entry<V> {
+    /// Flags the value for [`CacheStrategy::debug_validate`], after it was
+    /// modified in place.
+    fn mark_modified(&self) {
+        #[cfg(debug_assertions)]
+        self.modified.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the value of the given slot contents, which must hold one.
+    fn val(opt: &Option<Entry<V>>) -> &V {
+        opt.as_ref().and_then(|entry| entry.val.as_ref()).unwrap()
+    }
+
+    /// Returns the value of the given slot contents, which must hold one.
+    fn val_mut(opt: &mut Option<Entry<V>>) -> &mut V {
+        opt.as_mut().and_then(|entry| entry.val.as_mut()).unwrap()
+    }
+
+    /// Checks whether the given slot contents hold a value.
+    fn has_val(opt: &Option<Entry<V>>) -> bool {
+        matches!(opt, Some(Entry { val: Some(_), .. }))
+    }
+}
+
+/// A cache slot, which may hold an entry.
+///
+/// The slot's generation is bumped whenever its write lock is taken, so that
+/// copies of its value taken at some generation (see [`Cache::read_local`])
+/// can tell whether the value may have changed since.
+///
+/// Writers take the slot's upgrade token before its write lock, and
+/// upgradable readers hold it for as long as they live, so that no writer may
+/// take the slot while one of them upgrades (see [`UpgradableReadRef`]).
+///
+/// Poisoning is ignored: slot contents are only ever replaced whole, so a
+/// panic while the slot is locked leaves them coherent, if possibly holding a
+/// value the panicking writer was halfway through modifying.
+struct Slot<V> {
+    lock: RwLock<Option<Entry<V>>>,
+    generation: AtomicU64,
+    upgrade: Mutex<()>,
+}
+
+impl<V> Slot<V> {
+    fn new() -> Slot<V> {
+        Slot {
+            lock: RwLock::new(None),
+            generation: AtomicU64::new(0),
+            upgrade: Mutex::new(()),
+        }
+    }
+
+    fn upgrade_token(&self) -> MutexGuard<'_, ()> {
+        self.upgrade.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Option<Entry<V>>> {
+        self.lock.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns `None` if the slot is locked for write.
+    fn try_read(&self) -> Option<RwLockReadGuard<'_, Option<Entry<V>>>> {
+        match self.lock.try_read() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::WouldBlock) => None,
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        }
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Option<Entry<V>>> {
+        let token = self.upgrade_token();
+        self.write_with_token(&token)
+    }
+
+    /// Takes the write lock on behalf of the holder of the upgrade token.
+    fn write_with_token(&self, _: &MutexGuard<'_, ()>) -> RwLockWriteGuard<'_, Option<Entry<V>>> {
+        let guard = self.lock.write().unwrap_or_else(PoisonError::into_inner);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        guard
+    }
+
+    /// Returns `None` if the slot is locked, or held by an upgradable reader.
+    fn try_write(&self) -> Option<RwLockWriteGuard<'_, Option<Entry<V>>>> {
+        let _token = match self.upgrade.try_lock() {
+            Ok(token) => token,
+            Err(TryLockError::WouldBlock) => return None,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        };
+        let guard = self.probe()?;
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        Some(guard)
+    }
+
+    /// Like [`Slot::try_write`], but leaves the generation alone, for
+    /// diagnostics which only inspect the slot.
+    fn probe(&self) -> Option<RwLockWriteGuard<'_, Option<Entry<V>>>> {
+        match self.lock.try_write() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::WouldBlock) => None,
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        }
     }
 }
 
 /// A read-only shared view over a cache entry's value.
-pub struct ReadRef<'a, V>(RwLockReadGuard<'a, Option<V>>);
+pub struct ReadRef<'a, V> {
+    guard: RwLockReadGuard<'a, Option<Entry<V>>>,
+    _token: GuardToken<'a>,
+}
+
+impl<'a, V> ReadRef<'a, V> {
+    fn new(guard: RwLockReadGuard<'a, Option<Entry<V>>>, token: GuardToken<'a>) -> ReadRef<'a, V> {
+        paranoid_assert!(Entry::has_val(&guard), "read guard over an empty slot");
+        ReadRef {
+            guard,
+            _token: token,
+        }
+    }
+}
 
 impl<V> Deref for ReadRef<'_, V> {
     type Target = V;
 
     fn deref(&self) -> &Self::Target {
-        self.0.as_ref().unwrap()
+        Entry::val(&self.guard)
     }
 }
 
 /// a write exclusive view over a cache entry's value.
-pub struct WriteRef<'a, V>(RwLockWriteGuard<'a, Option<V>>);
+///
+/// The guard keeps track of whether the value may have been modified, i.e.,
+/// whether it was ever mutably dereferenced (or explicitly marked as such).
+pub struct WriteRef<'a, V> {
+    guard: RwLockWriteGuard<'a, Option<Entry<V>>>,
+    modified: bool,
+    _token: GuardToken<'a>,
+}
+
+impl<'a, V> WriteRef<'a, V> {
+    fn new(
+        guard: RwLockWriteGuard<'a, Option<Entry<V>>>,
+        token: GuardToken<'a>,
+    ) -> WriteRef<'a, V> {
+        paranoid_assert!(Entry::has_val(&guard), "write guard over an empty slot");
+        WriteRef {
+            guard,
+            modified: false,
+            _token: token,
+        }
+    }
+
+    /// Returns whether the value was mutably accessed through this guard, or
+    /// explicitly marked as modified.
+    pub fn was_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Marks the value as modified, e.g. after mutating it through interior
+    /// mutability.
+    pub fn mark_dirty(&mut self) {
+        self.modified = true;
+    }
+
+    /// Converts the guard into a read guard over the same value, without
+    /// unlocking the slot in between, so that no writer may replace the value
+    /// meanwhile.
+    pub fn downgrade(self) -> ReadRef<'a, V> {
+        paranoid_assert!(
+            Entry::has_val(&self.guard),
+            "write guard left its slot empty"
+        );
+        self.mark_for_check();
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped nor used again, so each field is
+        // moved out exactly once.
+        let (guard, mut token) = unsafe { (ptr::read(&this.guard), ptr::read(&this._token)) };
+        token.downgrade();
+        ReadRef::new(RwLockWriteGuard::downgrade(guard), token)
+    }
+
+    /// Flags the value for [`CacheStrategy::debug_validate`] if it was
+    /// modified through this guard.
+    fn mark_for_check(&self) {
+        if let Some(entry) = self.guard.as_ref().filter(|_| self.modified) {
+            entry.mark_modified();
+        }
+    }
+}
+
+impl<V> Drop for WriteRef<'_, V> {
+    fn drop(&mut self) {
+        // Writers only get `&mut V`, so they can't ever empty the slot.
+        paranoid_assert!(
+            Entry::has_val(&self.guard),
+            "write guard left its slot empty"
+        );
+        self.mark_for_check();
+    }
+}
 
 impl<V> Deref for WriteRef<'_, V> {
     type Target = V;
 
     fn deref(&self) -> &Self::Target {
-        self.0.as_ref().unwrap()
+        Entry::val(&self.guard)
     }
 }
 
 impl<V> DerefMut for WriteRef<'_, V> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.as_mut().unwrap()
+        self.modified = true;
+        Entry::val_mut(&mut self.guard)
     }
 }
 
+/// A value displaced from its slot by [`Cache::replace`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replaced<V> {
+    pub val: V,
+    /// Whether the value was the replacing key's own, rather than that of a
+    /// colliding key.
+    pub matched: bool,
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::test_utils::{TestHashBuilder, TestStrategy};
+    use std::panic::{self, AssertUnwindSafe};
+
+    use crate::test_utils::{TestHashBuilder, TestStrategy, Tweaked, NOT_FOUND};
 
     use super::*;
 
@@ -191,6 +1913,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_write_ref_was_modified() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut data = c.write(&1).unwrap();
+        assert_eq!(&*data, "1one");
+        assert!(!data.was_modified());
+        data.push_str("-mod");
+        assert!(data.was_modified());
+        drop(data);
+
+        let mut data = c.write(&1).unwrap();
+        assert!(!data.was_modified());
+        data.mark_dirty();
+        assert!(data.was_modified());
+    }
+
+    #[test]
+    fn test_write_or_insert_with() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        *c.write_or_insert_with(&1, || "1new".to_string()) = "1newer".to_string();
+        drop(c.write_or_insert_with(&5, || "5new".to_string()));
+        let data = c.write_or_insert_with(&5, || unreachable!());
+        assert_eq!(&*data, "5new");
+        drop(data);
+        assert_eq!(c.clone_strategy().count(), 0);
+        c.check_invariants();
+    }
+
+    #[test]
+    fn test_write_ref_downgrade() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut data = c.write(&1).unwrap();
+        data.push_str("-mod");
+        let data = data.downgrade();
+        assert_eq!(&*c.try_read(&1).unwrap().unwrap(), "1one-mod");
+        assert!(c.try_write(&1).unwrap().is_none());
+        assert_eq!(&*data, "1one-mod");
+        #[cfg(feature = "debug-guards")]
+        assert_eq!(c.outstanding_guards()[0].kind, GuardKind::Read);
+        drop(data);
+        c.check_invariants();
+    }
+
     #[test]
     fn test_read_diff_keys() {
         let s = TestStrategy::default();
@@ -233,11 +2004,492 @@ mod tests {
             assert_eq!(&*s1, "1one");
         }
     }
+
+    #[test]
+    fn test_check_invariants() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        c.check_invariants();
+        drop(c.read(&1).unwrap());
+        drop(c.write(&2).unwrap());
+        c.check_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "slot 1 is still locked")]
+    fn test_check_invariants_outstanding_guard() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let _guard = c.read(&1).unwrap();
+        c.check_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "outstanding guards")]
+    fn test_assert_no_outstanding_guards() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        c.assert_no_outstanding_guards();
+        let _guard = c.write(&2).unwrap();
+        c.assert_no_outstanding_guards();
+    }
+
+    #[test]
+    #[cfg(feature = "debug-guards")]
+    fn test_outstanding_guards() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let r = c.read(&1).unwrap();
+        let w = c.write(&2).unwrap();
+        let guards = c.outstanding_guards();
+        assert_eq!(guards.len(), 2);
+        assert_eq!((guards[0].slot, guards[0].kind), (1, GuardKind::Read));
+        assert_eq!((guards[1].slot, guards[1].kind), (2, GuardKind::Write));
+        assert_eq!(c.warn_old_guards(std::time::Duration::ZERO), 2);
+
+        drop((r, w));
+        assert!(c.outstanding_guards().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-guards")]
+    fn test_expired_write_leases() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let _r = c.read(&1).unwrap();
+        let w = c.write(&2).unwrap();
+        assert!(c.expired_write_leases().is_empty());
+
+        c.set_write_lease(Some(Duration::ZERO));
+        let expired = c.expired_write_leases();
+        assert_eq!(expired.len(), 1);
+        assert_eq!((expired[0].slot, expired[0].kind), (2, GuardKind::Write));
+
+        drop(w);
+        assert!(c.expired_write_leases().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-guards")]
+    fn test_last_writer() {
+        use std::thread;
+
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        assert!(c.last_writer(2).is_none());
+        let writer = c.last_writer(1).unwrap();
+        assert_eq!(writer.thread, thread::current().id());
+
+        thread::scope(|scope| {
+            thread::Builder::new()
+                .name("evictor".to_string())
+                .spawn_scoped(scope, || drop(c.write(&5).unwrap()))
+                .unwrap();
+        });
+        let writer = c.last_writer(1).unwrap();
+        assert_eq!(writer.thread_name.as_deref(), Some("evictor"));
+    }
+
+    #[test]
+    fn test_invalidate_many() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        drop(c.read(&3).unwrap());
+
+        // `5` shares the slot of `1`, but isn't resident.
+        assert_eq!(c.invalidate_many(&[5, 2, 1, 4, 2]), 2);
+        assert_eq!(c.map_values(|_, _| ()), 1);
+        drop(c.read(&1).unwrap());
+        assert_eq!(c.clone_strategy().count(), 4);
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        assert_eq!(c.invalidate(&5), None);
+        assert_eq!(c.invalidate(&1).as_deref(), Some("1one"));
+        assert_eq!(c.invalidate(&1), None);
+
+        drop(c.read(&1).unwrap());
+        assert_eq!(c.clone_strategy().count(), 2);
+    }
+
+    #[test]
+    fn test_clear() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        c.clear();
+        assert!(c.peek(&1).is_none());
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        assert_eq!(c.clone_strategy().count(), 4);
+    }
+
+    #[test]
+    fn test_contains() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        assert!(!c.contains(&1));
+        drop(c.read(&1).unwrap());
+        assert!(c.contains(&1));
+        assert!(!c.contains(&5));
+        assert_eq!(c.clone_strategy().count(), 1);
+    }
+
+    #[test]
+    fn test_for_each_resident() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&3).unwrap());
+        drop(c.read(&1).unwrap());
+        let mut resident = Vec::new();
+        let visited = c.for_each_resident(|hash, val| resident.push((hash, val.clone())));
+        assert_eq!(visited, 2);
+        assert_eq!(
+            resident,
+            [(1, "1one".to_string()), (3, "3three".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_retain() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        for key in [1, 2, 3] {
+            drop(c.read(&key).unwrap());
+        }
+        assert_eq!(c.retain(|val| val.ends_with('e')), 1);
+        assert!(c.contains(&1) && c.contains(&3));
+        assert!(!c.contains(&2));
+        assert_eq!(c.occupied(), 2);
+    }
+
+    #[test]
+    fn test_try_read_and_write() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(&*c.try_read(&1).unwrap().unwrap(), "1one");
+        {
+            let _w = c.try_write(&1).unwrap().unwrap();
+            assert!(c.try_read(&1).unwrap().is_none());
+            assert!(c.try_write(&5).unwrap().is_none());
+        }
+        let _r = c.read(&1).unwrap();
+        assert!(c.try_read(&1).unwrap().is_some());
+        assert!(c.try_write(&1).unwrap().is_none());
+        assert_eq!(c.clone_strategy().count(), 1);
+    }
+
+    #[test]
+    fn test_poisoning_recovery() {
+        struct Panicking(TestStrategy);
+
+        impl CacheStrategy for Panicking {
+            type Key = u32;
+            type Val = String;
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                assert_ne!(*key, 7, "failed to load 7");
+                self.0.load(key)
+            }
+
+            fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+                TestStrategy::match_kv(key, val)
+            }
+        }
+
+        let c = Cache::<Panicking, TestHashBuilder>::new::<4>(Panicking(TestStrategy::default()));
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut w = c.write(&1).unwrap();
+            w.push_str("-mod");
+            panic!("writer failed");
+        }));
+        assert!(res.is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| c.read(&7))).is_err());
+
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+        assert_eq!(&*c.read(&3).unwrap(), "3three");
+        assert_eq!(c.into_strategy().0.count(), 2);
+    }
+
+    #[test]
+    fn test_bump_epoch() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read_local(&2).unwrap());
+        c.bump_epoch();
+        assert!(!c.contains(&1));
+        assert_eq!(c.for_each_resident(|_, _| ()), 0);
+        assert_eq!(c.occupied(), 2);
+
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(&*c.read_local(&2).unwrap(), "2two");
+        assert_eq!(c.clone_strategy().count(), 4);
+        assert_eq!(c.occupied(), 2);
+    }
+
+    #[test]
+    fn test_get_or_insert_with() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let err = c.get_or_insert_with(&1, || Err("unavailable")).err();
+        assert_eq!(err, Some("unavailable"));
+        let val = c.get_or_insert_with(&1, || Ok::<_, ()>("1inline".to_string()));
+        assert_eq!(&*val.unwrap(), "1inline");
+        let val = c.get_or_insert_with(&1, || -> Result<_, ()> { unreachable!() });
+        assert_eq!(&*val.unwrap(), "1inline");
+        assert_eq!(c.clone_strategy().count(), 0);
+    }
+
+    #[test]
+    fn test_map_values() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+
+        let mut hashes = Vec::new();
+        let visited = c.map_values(|hash, val| {
+            hashes.push(hash);
+            val.push_str("-mod");
+        });
+        assert_eq!(visited, 2);
+        assert_eq!(hashes, [1, 2]);
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+        assert_eq!(&*c.read(&2).unwrap(), "2two-mod");
+        assert_eq!(c.clone_strategy().count(), 2);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let capacity = "3".parse().unwrap();
+        let c = Cache::<TestStrategy, TestHashBuilder>::with_capacity(
+            TestStrategy::default(),
+            capacity,
+        );
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&4).unwrap());
+        drop(c.read(&1).unwrap());
+        assert_eq!(c.slot_for_hash(4), 1);
+        assert_eq!((c.capacity(), c.occupied()), (3, 1));
+        assert_eq!(c.clone_strategy().count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "cache capacity must be non-zero")]
+    fn test_zero_capacity() {
+        Cache::<TestStrategy, TestHashBuilder>::new::<0>(TestStrategy::default());
+    }
+
+    #[test]
+    fn test_insert() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(c.insert(&1, "1new".to_string()), None);
+        assert_eq!(&*c.read(&1).unwrap(), "1new");
+        assert_eq!(c.insert(&5, "5new".to_string()).as_deref(), Some("1new"));
+        assert_eq!(&*c.read(&5).unwrap(), "5new");
+        assert_eq!(c.clone_strategy().count(), 0);
+    }
+
+    #[test]
+    fn test_replace() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(c.replace(&1, "1new".to_string()), None);
+        let replaced = c.replace(&1, "1newer".to_string()).unwrap();
+        assert_eq!((replaced.val.as_str(), replaced.matched), ("1new", true));
+        let replaced = c.replace(&5, "5new".to_string()).unwrap();
+        assert_eq!((replaced.val.as_str(), replaced.matched), ("1newer", false));
+        assert_eq!(&*c.read(&5).unwrap(), "5new");
+    }
+
+    #[test]
+    fn test_slot_for_hash() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(c.slot_for_hash(5), 1);
+        assert_eq!(c.slot_for_hash(u64::MAX), 3);
+        assert_eq!(c.write(&0).err().unwrap().slot(), c.slot_for_hash(0));
+    }
+
+    #[test]
+    fn test_on_divergence() {
+        struct Auditing(TestStrategy, Vec<(String, String)>);
+
+        impl CacheStrategy for Auditing {
+            type Key = u32;
+            type Val = String;
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                self.0.load(key)
+            }
+
+            fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+                TestStrategy::match_kv(key, val)
+            }
+
+            fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+                a == b
+            }
+
+            fn on_divergence(&mut self, _: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+                self.1.push((stale.clone(), fresh.clone()));
+            }
+        }
+
+        let s = Auditing(TestStrategy::default(), Vec::new());
+        let c = Cache::<Auditing, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        // A collision isn't a stale value.
+        drop(c.read(&5).unwrap());
+        drop(c.read(&1).unwrap());
+        *c.write(&1).unwrap() = "5five".to_string();
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+
+        // Neither is a reload into an identical value.
+        assert_eq!(c.identical_reloads(), 0);
+        c.bump_epoch();
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(c.identical_reloads(), 1);
+
+        let s = c.into_strategy();
+        assert_eq!(s.1, [("5five".to_string(), "1one".to_string())]);
+        assert_eq!(s.0.count(), 5);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_debug_validate() {
+        struct Checked(TestStrategy, Mutex<Vec<String>>);
+
+        impl CacheStrategy for Checked {
+            type Key = u32;
+            type Val = String;
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                self.0.load(key)
+            }
+
+            fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+                TestStrategy::match_kv(key, val)
+            }
+
+            fn debug_validate(&self, _: &Self::Key, val: &Self::Val) {
+                self.1.lock().unwrap().push(val.clone());
+            }
+        }
+
+        let s = Checked(TestStrategy::default(), Mutex::default());
+        let c = Cache::<Checked, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&1).unwrap());
+        c.write(&1).unwrap().push_str("-mod");
+        // Unmodified guards leave the value checked.
+        drop(c.write(&1).unwrap());
+        drop(c.read(&1).unwrap());
+        drop(c.read(&1).unwrap());
+        let mut w = c.write(&2).unwrap();
+        w.push_str("-mod");
+        drop(w.downgrade());
+        assert!(c.contains(&2));
+        c.map_values(|_, val| val.push_str("-map"));
+        assert!(c.contains(&1));
+        assert!(c.contains(&1));
+
+        let checked = c.into_strategy().1.into_inner().unwrap();
+        assert_eq!(
+            checked,
+            ["1one", "1one-mod", "2two", "2two-mod", "1one-mod-map"]
+        );
+    }
+
+    #[test]
+    fn test_warm() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        drop(c.read(&1).unwrap());
+        assert_eq!(c.warm([1, 2, 0, 3]), 2);
+        assert!(c.contains(&2) && c.contains(&3));
+        assert_eq!(c.clone_strategy().count(), 4);
+    }
+
+    #[test]
+    fn test_initial_keys() {
+        // `0` fails to load, which must not prevent construction.
+        let s = Tweaked::preloading(&[1, 2, 0]);
+        let c = Cache::<Tweaked, TestHashBuilder>::new::<4>(s);
+        assert_eq!(c.into_strategy().count(), 3);
+
+        let s = Tweaked::preloading(&[1, 2, 0]);
+        let c = Cache::<Tweaked, TestHashBuilder>::new::<4>(s);
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(&*c.read(&2).unwrap(), "2two");
+        assert_eq!(c.into_strategy().count(), 3);
+    }
+
+    #[test]
+    fn test_read_optional() {
+        let s = Tweaked::<NOT_FOUND>::default();
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+        assert!(c.read_optional(&0).unwrap().is_none());
+        assert!(c.read_optional(&0).unwrap().is_none());
+        assert_eq!(c.strategy().count(), 1);
+
+        // Plain reads don't trust the remembered absence.
+        assert!(c.read(&0).is_err());
+        assert_eq!(c.strategy().count(), 2);
+
+        assert_eq!(&*c.read_optional(&4).unwrap().unwrap(), "4four");
+        assert!(c.read_optional(&0).unwrap().is_none());
+        assert_eq!(c.strategy().count(), 4);
+        assert_eq!(c.map_values(|_, _| ()), 0);
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod test_utils {
-    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::{
+        cell::Cell,
+        hash::Hasher,
+        sync::atomic::{AtomicU32, Ordering},
+    };
 
     use super::*;
 
@@ -268,6 +2520,7 @@ pub(crate) mod test_utils {
         fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
             self.count.fetch_add(1, Ordering::SeqCst);
             Ok(match key {
+                0 => return Err(()),
                 1 => "1one",
                 2 => "2two",
                 3 => "3three",
@@ -283,7 +2536,83 @@ pub(crate) mod test_utils {
         }
     }
 
+    /// Flags selecting how a [`Tweaked`] strategy departs from
+    /// [`TestStrategy`].
+    pub type Tweaks = u8;
+
+    /// Inverts `match_kv`, so that every value conflicts with its own key.
+    pub const INVERTED: Tweaks = 1;
+
+    /// Classifies every load error as [`ErrorKind::NotFound`].
+    pub const NOT_FOUND: Tweaks = 2;
+
+    thread_local! {
+        static VALIDATION_FAILS: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Sets whether the fallible validation of [`Tweaked`] strategies fails on
+    /// the current thread.
+    pub fn fail_validation(fail: bool) {
+        VALIDATION_FAILS.set(fail);
+    }
+
+    /// A [`TestStrategy`] whose hooks are tweaked: the static ones by the
+    /// `TWEAKS` flags, and the keys loaded eagerly by `initial_keys`.
     #[derive(Default)]
+    pub struct Tweaked<const TWEAKS: Tweaks = 0> {
+        pub inner: TestStrategy,
+        pub initial_keys: Vec<u32>,
+    }
+
+    impl Tweaked {
+        pub fn preloading(keys: &[u32]) -> Tweaked {
+            Tweaked {
+                inner: TestStrategy::default(),
+                initial_keys: keys.to_vec(),
+            }
+        }
+    }
+
+    impl<const TWEAKS: Tweaks> Tweaked<TWEAKS> {
+        pub fn count(&self) -> u32 {
+            self.inner.count()
+        }
+    }
+
+    impl<const TWEAKS: Tweaks> CacheStrategy for Tweaked<TWEAKS> {
+        type Key = u32;
+        type Val = String;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.inner.load(key)
+        }
+
+        fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+            TestStrategy::match_kv(key, val) != (TWEAKS & INVERTED != 0)
+        }
+
+        fn try_validate(key: &Self::Key, val: &Self::Val) -> Result<Validation, Self::Err> {
+            if VALIDATION_FAILS.get() {
+                return Err(());
+            }
+            Ok(Self::validate(key, val))
+        }
+
+        fn classify_err(_: &Self::Err) -> ErrorKind {
+            if TWEAKS & NOT_FOUND != 0 {
+                ErrorKind::NotFound
+            } else {
+                ErrorKind::Transient
+            }
+        }
+
+        fn initial_keys(&self) -> Vec<Self::Key> {
+            self.initial_keys.clone()
+        }
+    }
+
+    #[derive(Clone, Default)]
     pub struct TestHashBuilder;
 
     impl BuildHasher for TestHashBuilder {
@@ -0,0 +1,118 @@
+//! The thread-local last hit, as used by [`Cache::read_local`].
+//!
+//! [`Cache::read_local`]: crate::Cache::read_local
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Source of the ids telling caches apart in the thread-local hit, which
+/// (unlike addresses) are never reused.
+static NEXT_CACHE_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn next_cache_id() -> u64 {
+    NEXT_CACHE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A value read by the current thread, along with where it was read from.
+struct LastHit {
+    cache_id: u64,
+    slot: usize,
+    hash: u64,
+    generation: u64,
+    epoch: u64,
+    val: Arc<dyn Any + Send + Sync>,
+}
+
+thread_local! {
+    static LAST_HIT: RefCell<Option<LastHit>> = const { RefCell::new(None) };
+}
+
+/// Returns the value last remembered by the current thread for the given
+/// slot, if it was read for the given hash and neither the slot's generation
+/// nor the cache's epoch have changed since.
+pub(crate) fn lookup<V>(
+    cache_id: u64,
+    slot: usize,
+    hash: u64,
+    generation: u64,
+    epoch: u64,
+) -> Option<Arc<V>>
+where
+    V: Send + Sync + 'static,
+{
+    LAST_HIT.with_borrow(|hit| {
+        let hit = hit.as_ref()?;
+        let fresh = (hit.cache_id, hit.slot, hit.hash, hit.generation, hit.epoch)
+            == (cache_id, slot, hash, generation, epoch);
+        fresh.then(|| hit.val.clone().downcast().ok()).flatten()
+    })
+}
+
+/// Remembers the given value as the current thread's last hit.
+pub(crate) fn remember<V>(
+    cache_id: u64,
+    slot: usize,
+    hash: u64,
+    generation: u64,
+    epoch: u64,
+    val: Arc<V>,
+) where
+    V: Send + Sync + 'static,
+{
+    LAST_HIT.set(Some(LastHit {
+        cache_id,
+        slot,
+        hash,
+        generation,
+        epoch,
+        val,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    #[test]
+    fn test_read_local() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let first = c.read_local(&1).unwrap();
+        let second = c.read_local(&1).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        c.write(&1).unwrap().push_str("-mod");
+        assert_eq!(&*c.read_local(&1).unwrap(), "1one-mod");
+
+        // Another cache must not observe this one's hit.
+        let other = Cache::<TestStrategy, TestHashBuilder>::new::<4>(TestStrategy::default());
+        assert_eq!(&*other.read_local(&1).unwrap(), "1one");
+        assert_eq!(other.clone_strategy().count(), 1);
+        assert_eq!(c.clone_strategy().count(), 1);
+    }
+
+    #[test]
+    fn test_read_local_across_diagnostics() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        // The diagnostics only probe the slots, so the hit stays fresh.
+        let first = c.read_local(&1).unwrap();
+        c.check_invariants();
+        c.assert_no_outstanding_guards();
+        let second = c.read_local(&1).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
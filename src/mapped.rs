@@ -0,0 +1,176 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::{Entry, ReadRef, WriteRef};
+
+/// A read-only shared view over a part of a cache entry's value, as obtained
+/// from [`ReadRef::map`].
+pub struct MappedReadRef<'a, V, T: ?Sized> {
+    _read: ReadRef<'a, V>,
+    /// Points into the value, which doesn't move while the slot is locked.
+    val: NonNull<T>,
+}
+
+// SAFETY: The guard only hands out shared references to the value's part.
+unsafe impl<V, T> Sync for MappedReadRef<'_, V, T>
+where
+    V: Sync,
+    T: ?Sized + Sync,
+{
+}
+
+impl<'a, V> ReadRef<'a, V> {
+    /// Narrows the guard to a part of the value, e.g. one of its fields.
+    ///
+    /// This is an associated function, rather than a method, so that it
+    /// doesn't shadow a method of the value.
+    pub fn map<T, F>(this: ReadRef<'a, V>, f: F) -> MappedReadRef<'a, V, T>
+    where
+        T: ?Sized,
+        F: FnOnce(&V) -> &T,
+    {
+        let val = NonNull::from(f(&this));
+        MappedReadRef { _read: this, val }
+    }
+}
+
+impl<'a, V, T: ?Sized> MappedReadRef<'a, V, T> {
+    /// Narrows the guard further.
+    pub fn map<U, F>(this: MappedReadRef<'a, V, T>, f: F) -> MappedReadRef<'a, V, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        let val = NonNull::from(f(&this));
+        MappedReadRef {
+            _read: this._read,
+            val,
+        }
+    }
+}
+
+impl<V, T: ?Sized> Deref for MappedReadRef<'_, V, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The pointer was derived from a reference into the value,
+        // which is kept locked for read by `_read`.
+        unsafe { self.val.as_ref() }
+    }
+}
+
+/// A write exclusive view over a part of a cache entry's value, as obtained
+/// from [`WriteRef::map`].
+pub struct MappedWriteRef<'a, V, T: ?Sized> {
+    write: WriteRef<'a, V>,
+    /// Points into the value, which doesn't move while the slot is locked.
+    val: NonNull<T>,
+    /// Makes the guard invariant over `T`, as a `&mut T` is.
+    _invariant: PhantomData<&'a mut T>,
+}
+
+// SAFETY: The guard hands out references to the value's part only through
+// `&self` and `&mut self`, as a `&mut T` would.
+unsafe impl<V, T> Sync for MappedWriteRef<'_, V, T>
+where
+    V: Sync,
+    T: ?Sized + Sync,
+{
+}
+
+impl<'a, V> WriteRef<'a, V> {
+    /// Narrows the guard to a part of the value, e.g. one of its fields.
+    ///
+    /// Narrowing doesn't mark the value as modified; mutably dereferencing
+    /// the narrowed guard does.
+    ///
+    /// This is an associated function, rather than a method, so that it
+    /// doesn't shadow a method of the value.
+    pub fn map<T, F>(mut this: WriteRef<'a, V>, f: F) -> MappedWriteRef<'a, V, T>
+    where
+        T: ?Sized,
+        F: FnOnce(&mut V) -> &mut T,
+    {
+        let val = NonNull::from(f(Entry::val_mut(&mut this.guard)));
+        MappedWriteRef {
+            write: this,
+            val,
+            _invariant: PhantomData,
+        }
+    }
+}
+
+impl<'a, V, T: ?Sized> MappedWriteRef<'a, V, T> {
+    /// Narrows the guard further.
+    pub fn map<U, F>(mut this: MappedWriteRef<'a, V, T>, f: F) -> MappedWriteRef<'a, V, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        // SAFETY: As in `DerefMut`, without marking the value as modified.
+        let val = NonNull::from(f(unsafe { this.val.as_mut() }));
+        MappedWriteRef {
+            write: this.write,
+            val,
+            _invariant: PhantomData,
+        }
+    }
+
+    /// Returns whether the value was mutably accessed through the guard, or
+    /// the one it was narrowed from.
+    pub fn was_modified(&self) -> bool {
+        self.write.was_modified()
+    }
+}
+
+impl<V, T: ?Sized> Deref for MappedWriteRef<'_, V, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The pointer was derived from a reference into the value,
+        // which is kept locked for write by `write`.
+        unsafe { self.val.as_ref() }
+    }
+}
+
+impl<V, T: ?Sized> DerefMut for MappedWriteRef<'_, V, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.write.mark_dirty();
+        // SAFETY: As in `Deref`; `&mut self` makes the access exclusive.
+        unsafe { self.val.as_mut() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_map_guards() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let digit = ReadRef::map(c.read(&1).unwrap(), |val| &val[..1]);
+        assert_eq!(&*digit, "1");
+        let digit = MappedReadRef::map(digit, |digit| digit.as_bytes());
+        assert_eq!(&*digit, b"1");
+        drop(digit);
+
+        let mut name = WriteRef::map(c.write(&1).unwrap(), |val| &mut val[1..]);
+        assert!(!name.was_modified());
+        name.make_ascii_uppercase();
+        assert!(name.was_modified());
+        drop(name);
+
+        assert_eq!(&*c.read(&1).unwrap(), "1ONE");
+        c.check_invariants();
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use tracing::error;
+
+/// The crate-wide policy, as set by [`MisusePolicy::set`]. Zero stands for
+/// the default.
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+#[cfg(test)]
+thread_local! {
+    /// Overrides the crate-wide policy on the current thread, so that tests
+    /// may exercise another policy without affecting those running alongside.
+    static THREAD_POLICY: Cell<Option<MisusePolicy>> = const { Cell::new(None) };
+}
+
+/// How API misuse, e.g. dropping a [`WriteBatch`] without flushing it, is
+/// reported.
+///
+/// Defaults to [`MisusePolicy::Panic`] in debug builds and to
+/// [`MisusePolicy::Log`] in release ones, so that libraries embedding the
+/// cache catch misuse in tests without it bringing production down.
+///
+/// [`WriteBatch`]: crate::WriteBatch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MisusePolicy {
+    /// Misuse panics.
+    Panic,
+    /// Misuse is logged as an error and recovered from as documented by the
+    /// misused API.
+    Log,
+}
+
+impl MisusePolicy {
+    /// Returns the crate-wide policy.
+    pub fn current() -> MisusePolicy {
+        #[cfg(test)]
+        if let Some(policy) = THREAD_POLICY.get() {
+            return policy;
+        }
+        match POLICY.load(Ordering::Relaxed) {
+            1 => MisusePolicy::Panic,
+            2 => MisusePolicy::Log,
+            _ => MisusePolicy::default(),
+        }
+    }
+
+    /// Sets the crate-wide policy, for every cache.
+    pub fn set(policy: MisusePolicy) {
+        let repr = match policy {
+            MisusePolicy::Panic => 1,
+            MisusePolicy::Log => 2,
+        };
+        POLICY.store(repr, Ordering::Relaxed);
+    }
+}
+
+impl Default for MisusePolicy {
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            MisusePolicy::Panic
+        } else {
+            MisusePolicy::Log
+        }
+    }
+}
+
+/// Reports API misuse according to the crate-wide policy. Callers must go on
+/// to recover from it if this returns.
+pub(crate) fn report(args: fmt::Arguments<'_>) {
+    report_with(MisusePolicy::current(), args);
+}
+
+/// Reports API misuse according to the given policy, for callers which must
+/// prepare differently for a panic (e.g., by releasing guards first).
+pub(crate) fn report_with(policy: MisusePolicy, args: fmt::Arguments<'_>) {
+    match policy {
+        MisusePolicy::Panic => panic!("{args}"),
+        MisusePolicy::Log => error!("{args}"),
+    }
+}
+
+/// Runs `f` with the given policy in effect on the current thread only.
+#[cfg(test)]
+pub(crate) fn with_policy<R>(policy: MisusePolicy, f: impl FnOnce() -> R) -> R {
+    let prev = THREAD_POLICY.replace(Some(policy));
+    let res = f();
+    THREAD_POLICY.set(prev);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_misuse_policy_log() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        with_policy(MisusePolicy::Log, || {
+            let mut wb = c.write_batch();
+            wb.write(&1, |val| val.push_str("-mod")).unwrap();
+            drop(wb);
+        });
+
+        // The unflushed batch's entries were released.
+        c.check_invariants();
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+    }
+
+    #[test]
+    fn test_thread_policy() {
+        let default = MisusePolicy::current();
+        with_policy(MisusePolicy::Log, || {
+            assert_eq!(MisusePolicy::current(), MisusePolicy::Log);
+            report(format_args!("logged, not panicking"));
+            // Other threads keep the crate-wide policy.
+            let other = std::thread::spawn(MisusePolicy::current).join().unwrap();
+            assert_eq!(other, default);
+        });
+        assert_eq!(MisusePolicy::current(), default);
+    }
+}
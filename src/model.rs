@@ -0,0 +1,195 @@
+//! A reference model of the cache's observable behavior.
+//!
+//! The model tracks which key each slot holds and every key passed to the
+//! loader, so that a sequence of [`Op`]s applied to both a [`Cache`] and a
+//! [`Model`] can be compared step by step. Downstream crates may reuse it to
+//! test their strategies' validation (i.e., [`CacheStrategy::match_kv`]) and
+//! collision handling.
+//!
+//! [`Cache`]: crate::Cache
+//! [`CacheStrategy::match_kv`]: crate::CacheStrategy::match_kv
+
+/// An operation over the cache, mostly over a single key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op<K> {
+    Read(K),
+    Write(K),
+    /// Places the key without loading it.
+    Insert(K),
+    Invalidate(K),
+    Clear,
+}
+
+impl<K> Op<K> {
+    /// Returns the key this operation applies to, unless it applies to every
+    /// key.
+    pub fn key(&self) -> Option<&K> {
+        match self {
+            Op::Read(key) | Op::Write(key) | Op::Insert(key) | Op::Invalidate(key) => Some(key),
+            Op::Clear => None,
+        }
+    }
+}
+
+/// Whether an operation found its key resident.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Hit,
+    Miss,
+}
+
+/// The reference model: one optional key per slot plus the loader call log.
+#[derive(Clone, Debug)]
+pub struct Model<K> {
+    slots: Vec<Option<K>>,
+    loads: Vec<K>,
+}
+
+impl<K> Model<K>
+where
+    K: Clone + PartialEq,
+{
+    /// Constructs an empty model with the given number of slots.
+    pub fn new(capacity: usize) -> Model<K> {
+        Model {
+            slots: vec![None; capacity],
+            loads: Vec::new(),
+        }
+    }
+
+    /// Applies the operation, given the slot its key maps to (which is ignored
+    /// for [`Op::Clear`]), and returns whether the key was resident, or `None`
+    /// for [`Op::Clear`].
+    ///
+    /// Reads and writes behave the same: the key is loaded, replacing whatever
+    /// the slot held, unless the slot already holds it. Inserts replace it
+    /// without loading, while invalidations only empty the slot if it holds
+    /// the key.
+    pub fn apply(&mut self, slot: usize, op: &Op<K>) -> Option<Outcome> {
+        let Some(key) = op.key() else {
+            self.slots.fill(None);
+            return None;
+        };
+        let entry = &mut self.slots[slot];
+        let outcome = if entry.as_ref() == Some(key) {
+            Outcome::Hit
+        } else {
+            Outcome::Miss
+        };
+        match op {
+            Op::Read(_) | Op::Write(_) if outcome == Outcome::Miss => {
+                self.loads.push(key.clone());
+                *entry = Some(key.clone());
+            }
+            Op::Read(_) | Op::Write(_) => {}
+            Op::Insert(_) => *entry = Some(key.clone()),
+            Op::Invalidate(_) if outcome == Outcome::Hit => *entry = None,
+            Op::Invalidate(_) | Op::Clear => {}
+        }
+        Some(outcome)
+    }
+
+    /// Returns the key resident in the given slot, if any.
+    pub fn resident(&self, slot: usize) -> Option<&K> {
+        self.slots[slot].as_ref()
+    }
+
+    /// Returns every key loaded so far, in order.
+    pub fn loads(&self) -> &[K] {
+        &self.loads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::BuildHasher;
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    const CAPACITY: usize = 4;
+    const KEYS: [u32; 3] = [1, 2, 5];
+
+    /// Returns every sequence of operations up to the given length.
+    fn sequences(len: usize) -> Vec<Vec<Op<u32>>> {
+        let ops: Vec<_> = KEYS
+            .iter()
+            .flat_map(|&k| [Op::Read(k), Op::Write(k), Op::Insert(k), Op::Invalidate(k)])
+            .chain([Op::Clear])
+            .collect();
+        let mut all = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..len {
+            last = last
+                .iter()
+                .flat_map(|seq: &Vec<_>| {
+                    ops.iter().map(move |op| {
+                        let mut seq = seq.clone();
+                        seq.push(op.clone());
+                        seq
+                    })
+                })
+                .collect();
+            all.extend(last.iter().cloned());
+        }
+        all
+    }
+
+    fn slot_of(c: &Cache<TestStrategy, TestHashBuilder>, key: &u32) -> usize {
+        c.slot_for_hash(TestHashBuilder.hash_one(key))
+    }
+
+    #[test]
+    fn test_matches_model() {
+        for seq in sequences(4) {
+            let c =
+                Cache::<TestStrategy, TestHashBuilder>::new::<CAPACITY>(TestStrategy::default());
+            let mut model = Model::new(CAPACITY);
+
+            for op in &seq {
+                let before = c.clone_strategy().count();
+                let was_resident = match op {
+                    Op::Read(key) => {
+                        assert!(c.read(key).unwrap().starts_with(&key.to_string()));
+                        None
+                    }
+                    Op::Write(key) => {
+                        assert!(c.write(key).unwrap().starts_with(&key.to_string()));
+                        None
+                    }
+                    Op::Insert(key) => {
+                        c.insert(key, format!("{key}inserted"));
+                        None
+                    }
+                    Op::Invalidate(key) => Some(c.invalidate(key).is_some()),
+                    Op::Clear => {
+                        c.clear();
+                        None
+                    }
+                };
+                let loaded = c.clone_strategy().count() != before;
+
+                let slot = op.key().map_or(0, |key| slot_of(&c, key));
+                let expected = model.apply(slot, op);
+                let loads = matches!(op, Op::Read(_) | Op::Write(_));
+                assert_eq!(loaded, loads && expected == Some(Outcome::Miss), "{seq:?}");
+                if let Some(was_resident) = was_resident {
+                    assert_eq!(was_resident, expected == Some(Outcome::Hit), "{seq:?}");
+                }
+
+                for key in &KEYS {
+                    let slot = slot_of(&c, key);
+                    let expected = model.resident(slot) == Some(key);
+                    assert_eq!(c.contains(key), expected, "{seq:?}");
+                    let peeked = c.peek(key).map(|val| val.starts_with(&key.to_string()));
+                    assert_eq!(peeked, expected.then_some(true), "{seq:?}");
+                }
+            }
+            assert_eq!(model.loads().len() as u32, c.clone_strategy().count());
+        }
+    }
+}
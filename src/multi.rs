@@ -0,0 +1,136 @@
+use std::{error::Error, fmt};
+
+use crate::LoadError;
+
+/// An error when acquiring several values at once through
+/// [`Cache::read_many`] or [`Cache::write_many`].
+///
+/// [`Cache::read_many`]: crate::Cache::read_many
+/// [`Cache::write_many`]: crate::Cache::write_many
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiKeyError<E> {
+    /// The keys at the indices `first` and `second` of the given keys map to
+    /// the same slot, so their values can't be held at once.
+    SameSlot {
+        slot: usize,
+        first: usize,
+        second: usize,
+    },
+    /// The strategy failed to load one of the keys.
+    Load(LoadError<E>),
+}
+
+impl<E> From<LoadError<E>> for MultiKeyError<E> {
+    fn from(err: LoadError<E>) -> Self {
+        MultiKeyError::Load(err)
+    }
+}
+
+impl<E> fmt::Display for MultiKeyError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiKeyError::SameSlot {
+                slot,
+                first,
+                second,
+            } => {
+                write!(f, "keys #{first} and #{second} both map to slot {slot}")
+            }
+            MultiKeyError::Load(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E> Error for MultiKeyError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MultiKeyError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the given slots' indices sorted by slot, the order in which they
+/// must be locked so that concurrent multi-key acquisitions can't deadlock.
+/// Fails if two of the slots are the same.
+pub(crate) fn lock_order<E>(slots: &[usize]) -> Result<Vec<usize>, MultiKeyError<E>> {
+    let mut order: Vec<usize> = (0..slots.len()).collect();
+    order.sort_unstable_by_key(|&i| (slots[i], i));
+    for pair in order.windows(2) {
+        if slots[pair[0]] == slots[pair[1]] {
+            return Err(MultiKeyError::SameSlot {
+                slot: slots[pair[0]],
+                first: pair[0],
+                second: pair[1],
+            });
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_read_many() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let refs = c.read_many(&[3, 1, 2]).unwrap();
+        let vals: Vec<&str> = refs.iter().map(|val| val.as_str()).collect();
+        assert_eq!(vals, ["3three", "1one", "2two"]);
+        drop(refs);
+        assert_eq!(c.clone_strategy().count(), 3);
+
+        assert_eq!(
+            c.read_many(&[1, 2, 5]).err(),
+            Some(MultiKeyError::SameSlot {
+                slot: 1,
+                first: 0,
+                second: 2,
+            })
+        );
+        assert!(matches!(
+            c.read_many(&[2, 2]),
+            Err(MultiKeyError::SameSlot { slot: 2, .. })
+        ));
+        assert!(c.read_many(&[]).unwrap().is_empty());
+        c.check_invariants();
+    }
+
+    #[test]
+    fn test_write_many() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut refs = c.write_many(&[2, 1]).unwrap();
+        refs[0].push_str("-mod");
+        refs[1].push_str("-mod");
+        assert!(c.try_read(&1).unwrap().is_none());
+        drop(refs);
+
+        assert_eq!(&*c.read(&2).unwrap(), "2two-mod");
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+        assert!(matches!(
+            c.write_many(&[5, 1]),
+            Err(MultiKeyError::SameSlot {
+                slot: 1,
+                first: 0,
+                second: 1,
+            })
+        ));
+        c.check_invariants();
+    }
+}
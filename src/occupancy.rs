@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// A callback invoked with the number of occupied slots.
+pub(crate) type OccupancyCallback = Box<dyn Fn(usize) + Send + Sync>;
+
+/// Counts the slots holding a value, firing callbacks as the count rises
+/// above their watermarks.
+pub(crate) struct Occupancy {
+    occupied: AtomicUsize,
+    watermarks: Vec<Watermark>,
+}
+
+struct Watermark {
+    above: usize,
+    /// Whether the callback may fire, i.e., whether the count has been at or
+    /// below the watermark since it last fired.
+    armed: AtomicBool,
+    callback: OccupancyCallback,
+}
+
+impl Occupancy {
+    pub(crate) fn new() -> Occupancy {
+        Occupancy {
+            occupied: AtomicUsize::new(0),
+            watermarks: Vec::new(),
+        }
+    }
+
+    /// Registers a callback to fire once the count rises above `above`. It
+    /// doesn't fire again until the count falls back to `above` or less.
+    pub(crate) fn add_watermark(&mut self, above: usize, callback: OccupancyCallback) {
+        self.watermarks.push(Watermark {
+            above,
+            armed: AtomicBool::new(true),
+            callback,
+        });
+    }
+
+    /// Returns the number of slots holding a value.
+    pub(crate) fn occupied(&self) -> usize {
+        self.occupied.load(Ordering::Relaxed)
+    }
+
+    /// Updates the count for a slot which held a value (or not) and now holds
+    /// one (or not).
+    pub(crate) fn update(&self, had_val: bool, has_val: bool) {
+        match (had_val, has_val) {
+            (false, true) => {
+                let occupied = self.occupied.fetch_add(1, Ordering::Relaxed) + 1;
+                for mark in &self.watermarks {
+                    if occupied > mark.above && mark.armed.swap(false, Ordering::Relaxed) {
+                        (mark.callback)(occupied);
+                    }
+                }
+            }
+            (true, false) => {
+                let occupied = self.occupied.fetch_sub(1, Ordering::Relaxed) - 1;
+                for mark in &self.watermarks {
+                    if occupied <= mark.above {
+                        mark.armed.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy, Tweaked},
+        CacheBuilder,
+    };
+
+    #[test]
+    fn test_occupancy_watermark() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let c = CacheBuilder::new(TestStrategy::default(), 4)
+            .hasher(TestHashBuilder)
+            .on_occupancy_above(0.5, {
+                let fired = fired.clone();
+                move |occupied| fired.lock().unwrap().push(occupied)
+            })
+            .build();
+
+        drop(c.read(&1).unwrap());
+        drop(c.read(&2).unwrap());
+        assert!(fired.lock().unwrap().is_empty());
+        drop(c.read(&3).unwrap());
+        drop(c.read(&4).unwrap());
+        assert_eq!(*fired.lock().unwrap(), [3]);
+
+        // Replacing a value leaves the occupancy as is.
+        drop(c.read(&5).unwrap());
+        assert_eq!(c.invalidate_many(&[3, 4]), 2);
+        drop(c.read(&3).unwrap());
+        assert_eq!(*fired.lock().unwrap(), [3, 3]);
+        assert_eq!(c.occupied(), 3);
+    }
+
+    #[test]
+    fn test_occupancy_watermark_on_preload() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let c = CacheBuilder::new(Tweaked::preloading(&[1, 2, 3]), 4)
+            .hasher(TestHashBuilder)
+            .on_occupancy_above(0.5, {
+                let fired = fired.clone();
+                move |occupied| fired.lock().unwrap().push(occupied)
+            })
+            .build();
+        assert_eq!(*fired.lock().unwrap(), [3]);
+
+        // The watermark was disarmed by the preload.
+        drop(c.read(&4).unwrap());
+        assert_eq!(*fired.lock().unwrap(), [3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "occupancy watermark must be in (0, 1)")]
+    fn test_occupancy_watermark_nan() {
+        CacheBuilder::new(TestStrategy::default(), 4).on_occupancy_above(f64::NAN, |_| ());
+    }
+
+    #[test]
+    #[should_panic(expected = "occupancy watermark must be in (0, 1)")]
+    fn test_occupancy_watermark_full() {
+        CacheBuilder::new(TestStrategy::default(), 4).on_occupancy_above(1.0, |_| ());
+    }
+}
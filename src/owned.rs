@@ -0,0 +1,156 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    mem,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use crate::{Cache, CacheStrategy, LoadError, ReadRef, WriteRef};
+
+/// A read-only shared view over a cache entry's value, keeping the cache
+/// alive, as obtained from [`Cache::read_owned`].
+pub struct OwnedReadRef<S, H = RandomState>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    // Declared first so that it is dropped before the cache it borrows.
+    guard: ReadRef<'static, S::Val>,
+    _cache: Arc<Cache<S, H>>,
+}
+
+/// A write exclusive view over a cache entry's value, keeping the cache
+/// alive, as obtained from [`Cache::write_owned`].
+pub struct OwnedWriteRef<S, H = RandomState>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    // Declared first so that it is dropped before the cache it borrows.
+    guard: WriteRef<'static, S::Val>,
+    _cache: Arc<Cache<S, H>>,
+}
+
+impl<S, H> Cache<S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    S::Val: 'static,
+    H: BuildHasher,
+{
+    /// Like [`Cache::read`], but the returned guard keeps the cache alive
+    /// instead of borrowing it, so that it may outlive the `Arc` it came from.
+    pub fn read_owned(
+        self: &Arc<Self>,
+        key: &S::Key,
+    ) -> Result<OwnedReadRef<S, H>, LoadError<S::Err>> {
+        let guard = self.read(key)?;
+        // SAFETY: The guard borrows the cache, which lives behind the `Arc`
+        // (so it doesn't move) and is kept alive until the guard is dropped.
+        let guard =
+            unsafe { mem::transmute::<ReadRef<'_, S::Val>, ReadRef<'static, S::Val>>(guard) };
+        Ok(OwnedReadRef {
+            guard,
+            _cache: self.clone(),
+        })
+    }
+
+    /// Like [`Cache::write`], but the returned guard keeps the cache alive
+    /// instead of borrowing it, so that it may outlive the `Arc` it came from.
+    pub fn write_owned(
+        self: &Arc<Self>,
+        key: &S::Key,
+    ) -> Result<OwnedWriteRef<S, H>, LoadError<S::Err>> {
+        let guard = self.write(key)?;
+        // SAFETY: As in `read_owned`.
+        let guard =
+            unsafe { mem::transmute::<WriteRef<'_, S::Val>, WriteRef<'static, S::Val>>(guard) };
+        Ok(OwnedWriteRef {
+            guard,
+            _cache: self.clone(),
+        })
+    }
+}
+
+impl<S, H> Deref for OwnedReadRef<S, H>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    type Target = S::Val;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<S, H> OwnedWriteRef<S, H>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    /// Returns whether the value was mutably accessed through this guard, or
+    /// explicitly marked as modified.
+    pub fn was_modified(&self) -> bool {
+        self.guard.was_modified()
+    }
+
+    /// Marks the value as modified, e.g. after mutating it through interior
+    /// mutability.
+    pub fn mark_dirty(&mut self) {
+        self.guard.mark_dirty();
+    }
+}
+
+impl<S, H> Deref for OwnedWriteRef<S, H>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    type Target = S::Val;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<S, H> DerefMut for OwnedWriteRef<S, H>
+where
+    S: CacheStrategy,
+    S::Val: 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestHashBuilder, TestStrategy};
+
+    use super::*;
+
+    type TestCache = Cache<TestStrategy, TestHashBuilder>;
+
+    fn read_detached(key: u32) -> OwnedReadRef<TestStrategy, TestHashBuilder> {
+        let c = Arc::new(TestCache::new::<4>(TestStrategy::default()));
+        c.write_owned(&key).unwrap().push_str("-mod");
+        c.read_owned(&key).unwrap()
+    }
+
+    #[test]
+    fn test_owned_guards() {
+        let data = read_detached(1);
+        assert_eq!(&*data, "1one-mod");
+        drop(data);
+
+        let c = Arc::new(TestCache::new::<4>(TestStrategy::default()));
+        let data = c.read_owned(&2).unwrap();
+        let weak = Arc::downgrade(&c);
+        drop(c);
+        assert!(weak.upgrade().is_some());
+        drop(data);
+        assert!(weak.upgrade().is_none());
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::hash_map::RandomState,
+    convert::Infallible,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{Cache, Composed, ErrorKind, Loader, Validator};
+
+/// The strategy of a [`Cache::read_through`] cache: values are loaded with
+/// `L` and stored along with their key, which validates them.
+pub type ReadThrough<L> = Composed<TagKeys<L>, ByTag>;
+
+/// The strategy of a [`Cache::memoizer`] cache.
+pub type Memoizer<F, K, V> = ReadThrough<Memoize<F, K, V>>;
+
+/// A value stored along with the key it was loaded for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tagged<K, V> {
+    key: K,
+    val: V,
+}
+
+impl<K, V> Tagged<K, V> {
+    /// Returns the key the value was loaded for.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the value.
+    pub fn into_inner(self) -> V {
+        self.val
+    }
+}
+
+impl<K, V> Deref for Tagged<K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        &self.val
+    }
+}
+
+impl<K, V> DerefMut for Tagged<K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.val
+    }
+}
+
+/// A loader tagging the values of `L` with their key.
+#[derive(Clone, Default)]
+pub struct TagKeys<L>(pub L);
+
+impl<L> Loader for TagKeys<L>
+where
+    L: Loader,
+    L::Key: Clone,
+{
+    type Key = L::Key;
+    type Val = Tagged<L::Key, L::Val>;
+    type Err = L::Err;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        let val = self.0.load(key)?;
+        Ok(Tagged {
+            key: key.clone(),
+            val,
+        })
+    }
+
+    fn classify_err(err: &Self::Err) -> ErrorKind {
+        L::classify_err(err)
+    }
+
+    fn initial_keys(&self) -> Vec<Self::Key> {
+        self.0.initial_keys()
+    }
+
+    fn same_val(a: &Self::Val, b: &Self::Val) -> bool {
+        L::same_val(&a.val, &b.val)
+    }
+
+    fn on_divergence(&mut self, key: &Self::Key, stale: &Self::Val, fresh: &Self::Val) {
+        self.0.on_divergence(key, &stale.val, &fresh.val);
+    }
+
+    fn debug_validate(&self, key: &Self::Key, val: &Self::Val) {
+        self.0.debug_validate(key, &val.val);
+    }
+}
+
+/// A validator comparing keys against the key a [`Tagged`] value was loaded
+/// for.
+pub struct ByTag;
+
+impl<K, V, E> Validator<K, Tagged<K, V>, E> for ByTag
+where
+    K: PartialEq,
+{
+    fn match_kv(key: &K, val: &Tagged<K, V>) -> bool {
+        *key != val.key
+    }
+}
+
+/// A loader over an infallible function of the key.
+pub struct Memoize<F, K, V> {
+    f: F,
+    _kv: PhantomData<fn(&K) -> V>,
+}
+
+impl<F, K, V> Loader for Memoize<F, K, V>
+where
+    F: FnMut(&K) -> V,
+{
+    type Key = K;
+    type Val = V;
+    type Err = Infallible;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        Ok((self.f)(key))
+    }
+}
+
+impl<L> Cache<ReadThrough<L>>
+where
+    L: Loader,
+    L::Key: Clone + PartialEq + Hash,
+{
+    /// Constructs a cache with the given number of slots which loads values
+    /// with the given loader, telling them apart by the key they were loaded
+    /// for. This fits most loaders whose values don't carry their key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn read_through(loader: L, capacity: usize) -> Cache<ReadThrough<L>> {
+        Cache::read_through_with_hasher(loader, capacity, RandomState::new())
+    }
+}
+
+impl<L, H> Cache<ReadThrough<L>, H>
+where
+    L: Loader,
+    L::Key: Clone + PartialEq + Hash,
+    H: BuildHasher,
+{
+    /// Like [`Cache::read_through`], but maps keys to slots using the given
+    /// hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn read_through_with_hasher(
+        loader: L,
+        capacity: usize,
+        hasher: H,
+    ) -> Cache<ReadThrough<L>, H> {
+        Cache::with_capacity_and_hasher(Composed::new(TagKeys(loader)), capacity, hasher)
+    }
+}
+
+impl<F, K, V> Cache<Memoizer<F, K, V>>
+where
+    F: FnMut(&K) -> V,
+    K: Clone + PartialEq + Hash,
+{
+    /// Constructs a cache with the given number of slots which memoizes the
+    /// given function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn memoizer(f: F, capacity: usize) -> Cache<Memoizer<F, K, V>> {
+        Cache::memoizer_with_hasher(f, capacity, RandomState::new())
+    }
+}
+
+impl<F, K, V, H> Cache<Memoizer<F, K, V>, H>
+where
+    F: FnMut(&K) -> V,
+    K: Clone + PartialEq + Hash,
+    H: BuildHasher,
+{
+    /// Like [`Cache::memoizer`], but maps keys to slots using the given
+    /// hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn memoizer_with_hasher(f: F, capacity: usize, hasher: H) -> Cache<Memoizer<F, K, V>, H> {
+        let loader = Memoize {
+            f,
+            _kv: PhantomData,
+        };
+        Cache::read_through_with_hasher(loader, capacity, hasher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::test_utils::TestHashBuilder;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Doubling {
+        count: u32,
+    }
+
+    impl Loader for Doubling {
+        type Key = u32;
+        type Val = u32;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.count += 1;
+            Ok(key * 2)
+        }
+    }
+
+    #[test]
+    fn test_read_through() {
+        let c = Cache::read_through_with_hasher(Doubling::default(), 2, TestHashBuilder);
+
+        assert_eq!(**c.read(&1).unwrap(), 2);
+        assert_eq!(**c.read(&3).unwrap(), 6);
+        assert_eq!(**c.read(&1).unwrap(), 2);
+        assert_eq!(c.read(&1).unwrap().key(), &1);
+
+        // `1` and `3` share a slot, so `1` was loaded again.
+        assert_eq!(c.into_strategy().into_loader().0.count, 3);
+    }
+
+    #[test]
+    fn test_memoizer() {
+        let calls = Cell::new(0);
+        let c = Cache::memoizer(
+            |key: &String| {
+                calls.set(calls.get() + 1);
+                key.len()
+            },
+            8,
+        );
+
+        assert_eq!(**c.read(&"abc".to_string()).unwrap(), 3);
+        assert_eq!(**c.read(&"abc".to_string()).unwrap(), 3);
+        assert_eq!(calls.get(), 1);
+    }
+}
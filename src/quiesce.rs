@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+/// The outcome of [`Cache::quiesce`].
+///
+/// [`Cache::quiesce`]: crate::Cache::quiesce
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuiesceReport {
+    /// The slots which were still write-locked (i.e., being written to or
+    /// loaded into) when the wait ended, in ascending order.
+    pub busy_slots: Vec<usize>,
+    /// Whether the strategy was still loading when the wait ended.
+    pub loading: bool,
+    /// For how long the cache was waited on.
+    pub waited: Duration,
+}
+
+impl QuiesceReport {
+    /// Returns whether the cache became quiescent before the timeout.
+    pub fn is_quiescent(&self) -> bool {
+        self.busy_slots.is_empty() && !self.loading
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread, time::Duration};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    #[test]
+    fn test_quiesce() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let r = c.read(&1).unwrap();
+        assert!(c.quiesce(Duration::ZERO).is_quiescent());
+
+        let w = c.write(&2).unwrap();
+        let report = c.quiesce(Duration::from_millis(5));
+        assert_eq!((report.busy_slots, report.loading), (vec![2], false));
+        assert!(report.waited >= Duration::from_millis(5));
+        drop(w);
+
+        let locked = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let _w = c.write(&3).unwrap();
+                locked.wait();
+                thread::sleep(Duration::from_millis(10));
+            });
+            locked.wait();
+            assert!(c.quiesce(Duration::from_secs(10)).is_quiescent());
+        });
+        drop(r);
+    }
+}
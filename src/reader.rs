@@ -0,0 +1,129 @@
+use std::hash::{BuildHasher, Hash};
+
+use crate::{Cache, CacheStrategy, CollisionStats, KeyStats, LoadError, ReadRef};
+
+/// A read-only view over a [`Cache`], for components which must never write
+/// to it.
+///
+/// [`CacheReader::read`] loads missing keys, unless the reader was made to
+/// never load with [`CacheReader::never_load`]. It then only returns resident
+/// values, as [`CacheReader::peek`] does.
+pub struct CacheReader<'c, S, H>
+where
+    S: CacheStrategy,
+{
+    cache: &'c Cache<S, H>,
+    loads: bool,
+}
+
+impl<'c, S, H> CacheReader<'c, S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    H: BuildHasher,
+{
+    pub(crate) fn new(cache: &'c Cache<S, H>) -> CacheReader<'c, S, H> {
+        CacheReader { cache, loads: true }
+    }
+
+    /// Returns a reader over the same cache which never loads, for components
+    /// which must not trigger loads either.
+    pub fn never_load(self) -> CacheReader<'c, S, H> {
+        CacheReader {
+            loads: false,
+            ..self
+        }
+    }
+
+    /// Returns whether [`CacheReader::read`] loads missing keys.
+    pub fn loads(&self) -> bool {
+        self.loads
+    }
+
+    /// Acquires the value by the given key, for read, loading it if needed.
+    /// Returns `None` only if the reader never loads and the key isn't
+    /// resident.
+    pub fn read(&self, key: &S::Key) -> Result<Option<ReadRef<'c, S::Val>>, LoadError<S::Err>> {
+        if self.loads {
+            self.cache.read(key).map(Some)
+        } else {
+            Ok(self.cache.peek(key))
+        }
+    }
+
+    /// Acquires the value by the given key, for read, if it is resident.
+    /// Never loads.
+    pub fn peek(&self, key: &S::Key) -> Option<ReadRef<'c, S::Val>> {
+        self.cache.peek(key)
+    }
+
+    /// Returns the number of slots, as [`Cache::capacity`] does.
+    pub fn capacity(&self) -> usize {
+        self.cache.capacity()
+    }
+
+    /// Returns the number of slots holding a value, as [`Cache::occupied`]
+    /// does.
+    pub fn occupied(&self) -> usize {
+        self.cache.occupied()
+    }
+
+    /// Returns the cache's collision stats, as [`Cache::collision_stats`]
+    /// does.
+    pub fn collision_stats(&self, top: usize) -> CollisionStats {
+        self.cache.collision_stats(top)
+    }
+
+    /// Returns the stats of the currently sampled keys, as
+    /// [`Cache::sampled_key_stats`] does.
+    pub fn sampled_key_stats(&self) -> Vec<KeyStats> {
+        self.cache.sampled_key_stats()
+    }
+}
+
+impl<S, H> Clone for CacheReader<'_, S, H>
+where
+    S: CacheStrategy,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S, H> Copy for CacheReader<'_, S, H> where S: CacheStrategy {}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestHashBuilder, TestStrategy};
+
+    use super::*;
+
+    #[test]
+    fn test_reader_peek() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+        let r = c.reader();
+
+        assert!(r.peek(&1).is_none());
+        assert_eq!(&*r.read(&1).unwrap().unwrap(), "1one");
+        assert_eq!(&*r.peek(&1).unwrap(), "1one");
+        assert!(r.peek(&5).is_none());
+        assert_eq!(c.clone_strategy().count(), 1);
+        assert_eq!((r.capacity(), r.occupied()), (4, 1));
+    }
+
+    #[test]
+    fn test_reader_never_load() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+        let r = c.reader().never_load();
+        assert!(!r.loads());
+
+        assert!(r.read(&1).unwrap().is_none());
+        drop(c.read(&1).unwrap());
+        assert_eq!(&*r.read(&1).unwrap().unwrap(), "1one");
+        assert!(r.read(&5).unwrap().is_none());
+        assert_eq!(c.clone_strategy().count(), 1);
+        assert_eq!(r.collision_stats(1), c.collision_stats(1));
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::Loader;
+
+/// A loader which loads values from requests derived from the keys, e.g.
+/// SQL queries or URLs, rather than from the keys themselves.
+///
+/// Wrapped in a [`Requested`], each key's request is derived once and
+/// memoized across loads. The [`Requested`] loader is then composed with a
+/// [`Validator`] into a strategy (see [`Composed`]).
+///
+/// [`Validator`]: crate::Validator
+/// [`Composed`]: crate::Composed
+pub trait RequestLoader {
+    type Key;
+    type Request;
+    type Val;
+    type Err;
+
+    /// Derives the request which loads the value for the given key.
+    fn to_request(&self, key: &Self::Key) -> Self::Request;
+
+    /// Loads the value for the given request.
+    fn load(&mut self, req: &Self::Request) -> Result<Self::Val, Self::Err>;
+}
+
+/// A [`Loader`] over a [`RequestLoader`], which memoizes the request of each
+/// key it loads.
+///
+/// The memo keeps one request per distinct key loaded until it is cleared
+/// (see [`Requested::clear_requests`]), so it suits bounded key spaces.
+pub struct Requested<L, H = RandomState>
+where
+    L: RequestLoader,
+{
+    loader: L,
+    requests: HashMap<L::Key, L::Request, H>,
+}
+
+impl<L> Requested<L>
+where
+    L: RequestLoader,
+{
+    /// Constructs a new strategy over the given loader.
+    pub fn new(loader: L) -> Requested<L> {
+        Requested::with_hasher(loader, RandomState::new())
+    }
+}
+
+impl<L, H> Requested<L, H>
+where
+    L: RequestLoader,
+{
+    /// Constructs a new strategy over the given loader, whose memo hashes keys
+    /// with the given hasher.
+    pub fn with_hasher(loader: L, hasher: H) -> Requested<L, H> {
+        Requested {
+            loader,
+            requests: HashMap::with_hasher(hasher),
+        }
+    }
+
+    /// Returns the loader.
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+
+    /// Returns the number of memoized requests.
+    pub fn memoized(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Drops every memoized request.
+    pub fn clear_requests(&mut self) {
+        self.requests.clear();
+    }
+
+    /// Returns the loader.
+    pub fn into_loader(self) -> L {
+        self.loader
+    }
+}
+
+impl<L, H> Loader for Requested<L, H>
+where
+    L: RequestLoader,
+    L::Key: Hash + Eq + Clone,
+    H: BuildHasher,
+{
+    type Key = L::Key;
+    type Val = L::Val;
+    type Err = L::Err;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        let loader = &mut self.loader;
+        let req = match self.requests.get(key) {
+            Some(req) => req,
+            None => {
+                let req = loader.to_request(key);
+                self.requests.entry(key.clone()).or_insert(req)
+            }
+        };
+        loader.load(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::{test_utils::TestHashBuilder, Cache, Composed, Validator};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Queries {
+        derived: Cell<u32>,
+        loads: u32,
+    }
+
+    impl RequestLoader for Queries {
+        type Key = i32;
+        type Request = String;
+        type Val = (i32, String);
+        type Err = ();
+
+        fn to_request(&self, key: &Self::Key) -> Self::Request {
+            self.derived.set(self.derived.get() + 1);
+            format!("SELECT name FROM t WHERE id = {key}")
+        }
+
+        fn load(&mut self, req: &Self::Request) -> Result<Self::Val, Self::Err> {
+            self.loads += 1;
+            let id = req.rsplit(' ').next().unwrap().parse().unwrap();
+            Ok((id, req.clone()))
+        }
+    }
+
+    struct ById;
+
+    impl<E> Validator<i32, (i32, String), E> for ById {
+        fn match_kv(key: &i32, val: &(i32, String)) -> bool {
+            *key != val.0
+        }
+    }
+
+    #[test]
+    fn test_requested() {
+        let s = Composed::<_, ById>::new(Requested::new(Queries::default()));
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+
+        assert_eq!(c.read(&1).unwrap().1, "SELECT name FROM t WHERE id = 1");
+        // Evicts 1, which is then reloaded from its memoized request.
+        drop(c.read(&5).unwrap());
+        drop(c.read(&1).unwrap());
+        // Values of past epochs are stale.
+        c.bump_epoch();
+        drop(c.read(&1).unwrap());
+
+        let s = c.into_strategy().into_loader();
+        assert_eq!(s.memoized(), 2);
+        assert_eq!((s.loader().derived.get(), s.loader().loads), (2, 4));
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// Decides which operations get their lock acquisition traced, so that hot
+/// caches don't flood the tracing backend. Loads and load failures are traced
+/// regardless.
+///
+/// Each thread counts down its own operations (across caches), so that
+/// sampling doesn't make threads contend over a shared counter.
+pub(crate) struct TraceSampler {
+    every: AtomicU32,
+}
+
+thread_local! {
+    /// How many of the thread's operations are left before the next sampled
+    /// one.
+    static COUNTDOWN: Cell<u32> = const { Cell::new(0) };
+}
+
+impl TraceSampler {
+    pub(crate) fn new() -> TraceSampler {
+        TraceSampler {
+            every: AtomicU32::new(1),
+        }
+    }
+
+    /// Traces one in every `every` operations from now on, or none if `every`
+    /// is zero.
+    pub(crate) fn set_every(&self, every: u32) {
+        self.every.store(every, Ordering::Relaxed);
+    }
+
+    /// Returns whether the current operation should be traced.
+    pub(crate) fn sample(&self) -> bool {
+        match self.every.load(Ordering::Relaxed) {
+            0 => false,
+            1 => true,
+            every => COUNTDOWN.with(|countdown| {
+                // A countdown left by a sparser rate is cut short.
+                let left = countdown.get().min(every - 1);
+                countdown.set(left.checked_sub(1).unwrap_or(every - 1));
+                left == 0
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_one_in_n() {
+        let s = TraceSampler::new();
+        assert!((0..4).all(|_| s.sample()));
+
+        s.set_every(3);
+        let sampled: Vec<_> = (0..7).map(|_| s.sample()).collect();
+        assert_eq!(sampled, [true, false, false, true, false, false, true]);
+
+        s.set_every(0);
+        assert!(!(0..4).any(|_| s.sample()));
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::Loader;
+
+/// A cache's loader over state shared with the loaders of other caches, e.g.
+/// a connection pool. Each cache gets its own implementation, so loads always
+/// know which cache they come from.
+///
+/// Wrapped in a [`Shared`] along with the shared state, it is then composed
+/// with a [`Validator`] into a strategy (see [`Composed`]).
+///
+/// [`Validator`]: crate::Validator
+/// [`Composed`]: crate::Composed
+pub trait SharedStrategy<S> {
+    type Key;
+    type Val;
+    type Err;
+
+    /// Loads the value for the given key, using the shared state.
+    fn load(&mut self, shared: &mut S, key: &Self::Key) -> Result<Self::Val, Self::Err>;
+}
+
+/// A [`Loader`] over a [`SharedStrategy`] and the state it shares with other
+/// caches' loaders.
+///
+/// The shared state is locked for the duration of each load, so loads of all
+/// the caches sharing it are serialized.
+pub struct Shared<S, T> {
+    shared: Arc<Mutex<S>>,
+    strategy: T,
+}
+
+impl<S, T> Shared<S, T> {
+    /// Constructs a new loader over the given shared state.
+    pub fn new(shared: Arc<Mutex<S>>, strategy: T) -> Shared<S, T> {
+        Shared { shared, strategy }
+    }
+
+    /// Returns the shared state.
+    pub fn shared(&self) -> &Arc<Mutex<S>> {
+        &self.shared
+    }
+
+    /// Returns the cache's own strategy.
+    pub fn strategy(&self) -> &T {
+        &self.strategy
+    }
+
+    /// Returns the cache's own strategy.
+    pub fn into_strategy(self) -> T {
+        self.strategy
+    }
+}
+
+impl<S, T> Clone for Shared<S, T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Shared::new(self.shared.clone(), self.strategy.clone())
+    }
+}
+
+impl<S, T> Loader for Shared<S, T>
+where
+    T: SharedStrategy<S>,
+{
+    type Key = T::Key;
+    type Val = T::Val;
+    type Err = T::Err;
+
+    fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+        // As with the cache's own strategy lock, a strategy which panicked
+        // while loading is trusted to have left the shared state usable.
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        self.strategy.load(&mut shared, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::TestHashBuilder, Cache, Composed, Validation, Validator};
+
+    use super::*;
+
+    /// A stand-in connection pool, logging the queries it runs.
+    #[derive(Default)]
+    struct Pool {
+        queries: Vec<String>,
+    }
+
+    struct Names;
+
+    impl SharedStrategy<Pool> for Names {
+        type Key = u32;
+        type Val = (u32, String);
+        type Err = ();
+
+        fn load(&mut self, pool: &mut Pool, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            pool.queries.push(format!("name {key}"));
+            Ok((*key, format!("name-{key}")))
+        }
+    }
+
+    struct Sizes;
+
+    impl SharedStrategy<Pool> for Sizes {
+        type Key = u32;
+        type Val = (u32, usize);
+        type Err = ();
+
+        fn load(&mut self, pool: &mut Pool, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            pool.queries.push(format!("size {key}"));
+            Ok((*key, *key as usize * 10))
+        }
+    }
+
+    /// Matches values tagged with their own key.
+    struct ByKey;
+
+    impl<T, E> Validator<u32, (u32, T), E> for ByKey {
+        fn match_kv(key: &u32, val: &(u32, T)) -> bool {
+            *key != val.0
+        }
+    }
+
+    #[test]
+    fn test_shared_validation() {
+        struct Unchecked;
+
+        impl SharedStrategy<Pool> for Unchecked {
+            type Key = u32;
+            type Val = u32;
+            type Err = ();
+
+            fn load(&mut self, _: &mut Pool, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                Ok(*key)
+            }
+        }
+
+        struct Failing;
+
+        impl Validator<u32, u32, ()> for Failing {
+            fn match_kv(key: &u32, val: &u32) -> bool {
+                key != val
+            }
+
+            fn try_validate(_: &u32, _: &u32) -> Result<Validation, ()> {
+                Err(())
+            }
+        }
+
+        let pool = Arc::new(Mutex::new(Pool::default()));
+        let s = Composed::<_, Failing>::new(Shared::new(pool, Unchecked));
+        let c = Cache::<_, TestHashBuilder>::new::<4>(s);
+        assert_eq!(c.insert(&1, 1), None);
+        assert!(c.read(&1).is_err());
+        assert!(!c.contains(&1));
+    }
+
+    #[test]
+    fn test_shared_strategy() {
+        let pool = Arc::new(Mutex::new(Pool::default()));
+        let names = Composed::<_, ByKey>::new(Shared::new(pool.clone(), Names));
+        let names = Cache::<_, TestHashBuilder>::new::<4>(names);
+        let sizes = Composed::<_, ByKey>::new(Shared::new(pool.clone(), Sizes));
+        let sizes = Cache::<_, TestHashBuilder>::new::<4>(sizes);
+
+        assert_eq!(names.read(&1).unwrap().1, "name-1");
+        assert_eq!(sizes.read(&1).unwrap().1, 10);
+        assert_eq!(names.read(&2).unwrap().1, "name-2");
+        assert_eq!(pool.lock().unwrap().queries, ["name 1", "size 1", "name 2"]);
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard, PoisonError, TryLockError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Why a load was shed by the cache instead of queuing for the strategy, as
+/// configured with [`CacheBuilder::max_pending_loads`] and
+/// [`CacheBuilder::max_load_wait`].
+///
+/// Shed loads fail with the strategy's error converted from this one, and
+/// classified as [`ErrorKind::Overloaded`].
+///
+/// [`CacheBuilder::max_pending_loads`]: crate::CacheBuilder::max_pending_loads
+/// [`CacheBuilder::max_load_wait`]: crate::CacheBuilder::max_load_wait
+/// [`ErrorKind::Overloaded`]: crate::ErrorKind::Overloaded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Overloaded {
+    /// The key had to be loaded, but too many loads were already pending.
+    TooManyPending { pending: usize },
+    /// The key had to be loaded, but the strategy stayed busy with other loads
+    /// for too long.
+    WaitedTooLong { waited: Duration },
+}
+
+impl fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Overloaded::TooManyPending { pending } => {
+                write!(f, "load shed, with {pending} loads already pending")
+            }
+            Overloaded::WaitedTooLong { waited } => {
+                write!(f, "load shed, after waiting for {waited:?}")
+            }
+        }
+    }
+}
+
+impl Error for Overloaded {}
+
+/// When loads are shed, and how to report it through the strategy's error.
+pub(crate) struct ShedPolicy<E> {
+    pub(crate) max_pending: usize,
+    pub(crate) max_wait: Option<Duration>,
+    pub(crate) to_err: fn(Overloaded) -> E,
+}
+
+/// Counts the loads which are running or waiting for the strategy, shedding
+/// those past the limits of its policy, if any.
+pub(crate) struct LoadQueue<E> {
+    pending: AtomicUsize,
+    policy: Option<ShedPolicy<E>>,
+}
+
+impl<E> LoadQueue<E> {
+    pub(crate) fn new(policy: Option<ShedPolicy<E>>) -> LoadQueue<E> {
+        LoadQueue {
+            pending: AtomicUsize::new(0),
+            policy,
+        }
+    }
+
+    /// Counts a load as pending until the returned guard is dropped, unless
+    /// too many loads are pending already. The check and the count are a
+    /// single atomic step, so concurrent loads can't overshoot the limit.
+    pub(crate) fn enter(&self) -> Result<PendingLoad<'_>, E> {
+        let Some(policy) = &self.policy else {
+            self.pending.fetch_add(1, Ordering::Relaxed);
+            return Ok(PendingLoad(&self.pending));
+        };
+        self.pending
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pending| {
+                (pending < policy.max_pending).then_some(pending + 1)
+            })
+            .map_err(|pending| (policy.to_err)(Overloaded::TooManyPending { pending }))?;
+        Ok(PendingLoad(&self.pending))
+    }
+
+    /// Locks the given strategy for a load, giving up once the policy's wait
+    /// limit elapses. Poisoning is ignored, as for any other strategy lock.
+    pub(crate) fn lock<'a, S>(&self, strategy: &'a Mutex<S>) -> Result<MutexGuard<'a, S>, E> {
+        let Some((max_wait, to_err)) = self
+            .policy
+            .as_ref()
+            .and_then(|policy| Some((policy.max_wait?, policy.to_err)))
+        else {
+            return Ok(strategy.lock().unwrap_or_else(PoisonError::into_inner));
+        };
+        let start = Instant::now();
+        loop {
+            match strategy.try_lock() {
+                Ok(guard) => return Ok(guard),
+                Err(TryLockError::Poisoned(err)) => return Ok(err.into_inner()),
+                Err(TryLockError::WouldBlock) => {}
+            }
+            let waited = start.elapsed();
+            if waited >= max_wait {
+                return Err(to_err(Overloaded::WaitedTooLong { waited }));
+            }
+            thread::sleep((max_wait - waited).min(STRATEGY_POLL_INTERVAL));
+        }
+    }
+}
+
+pub(crate) struct PendingLoad<'a>(&'a AtomicUsize);
+
+impl Drop for PendingLoad<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// How often a load waiting for the strategy under a wait limit polls it.
+const STRATEGY_POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        CacheBuilder, CacheStrategy, ErrorKind,
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestErr {
+        Failed,
+        Overloaded(Overloaded),
+    }
+
+    impl From<Overloaded> for TestErr {
+        fn from(err: Overloaded) -> Self {
+            TestErr::Overloaded(err)
+        }
+    }
+
+    /// Blocks loads of `1` until told to go on, after announcing them.
+    struct Blocking {
+        inner: TestStrategy,
+        started: Sender<()>,
+        go: Receiver<()>,
+    }
+
+    impl CacheStrategy for Blocking {
+        type Key = u32;
+        type Val = String;
+        type Err = TestErr;
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            if *key == 1 {
+                self.started.send(()).unwrap();
+                self.go.recv().unwrap();
+            }
+            self.inner.load(key).map_err(|()| TestErr::Failed)
+        }
+
+        fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+            TestStrategy::match_kv(key, val)
+        }
+    }
+
+    fn blocking() -> (Blocking, Receiver<()>, Sender<()>) {
+        let (started, started_rx) = mpsc::channel();
+        let (go_tx, go) = mpsc::channel();
+        let s = Blocking {
+            inner: TestStrategy::default(),
+            started,
+            go,
+        };
+        (s, started_rx, go_tx)
+    }
+
+    #[test]
+    fn test_load_queue() {
+        let queue = LoadQueue::new(Some(ShedPolicy {
+            max_pending: 1,
+            max_wait: None,
+            to_err: TestErr::from,
+        }));
+        let pending = queue.enter().ok().unwrap();
+        assert_eq!(
+            queue.enter().err(),
+            Some(TestErr::Overloaded(Overloaded::TooManyPending {
+                pending: 1
+            }))
+        );
+        drop(pending);
+        assert!(queue.enter().is_ok());
+    }
+
+    #[test]
+    fn test_max_pending_loads() {
+        let (s, started, go) = blocking();
+        let c = CacheBuilder::new(s, 4)
+            .hasher(TestHashBuilder)
+            .max_pending_loads(1)
+            .build();
+
+        thread::scope(|scope| {
+            let loading = scope.spawn(|| drop(c.read(&1).unwrap()));
+            started.recv().unwrap();
+            let err = c.read(&2).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::Overloaded);
+            assert_eq!(
+                err.into_err(),
+                TestErr::Overloaded(Overloaded::TooManyPending { pending: 1 })
+            );
+            go.send(()).unwrap();
+            loading.join().unwrap();
+        });
+
+        // Resident keys are served regardless, and loads resume once the
+        // queue drains.
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(&*c.read(&2).unwrap(), "2two");
+        assert_eq!(c.read(&0).err().unwrap().kind(), ErrorKind::Transient);
+    }
+
+    #[test]
+    fn test_max_load_wait() {
+        let (s, started, go) = blocking();
+        let c = CacheBuilder::new(s, 4)
+            .hasher(TestHashBuilder)
+            .max_load_wait(Duration::from_millis(1))
+            .build();
+
+        thread::scope(|scope| {
+            let loading = scope.spawn(|| drop(c.read(&1).unwrap()));
+            started.recv().unwrap();
+            let err = c.write(&2).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::Overloaded);
+            assert!(matches!(
+                err.into_err(),
+                TestErr::Overloaded(Overloaded::WaitedTooLong { waited })
+                    if waited >= Duration::from_millis(1)
+            ));
+            go.send(()).unwrap();
+            loading.join().unwrap();
+        });
+        assert_eq!(&*c.read(&2).unwrap(), "2two");
+        c.check_invariants();
+    }
+}
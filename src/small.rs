@@ -0,0 +1,252 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+};
+
+use tracing::info;
+
+use crate::{slot_index, CacheStrategy, LoadError, Operation, Validation};
+
+/// A small `Copy` value which round-trips through a `u64`, so that it may be
+/// stored in an atomic cell.
+pub trait AtomicValue: Copy {
+    fn into_bits(self) -> u64;
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_atomic_value {
+    ($($t:ty),*) => {
+        $(
+            impl AtomicValue for $t {
+                fn into_bits(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_bits(bits: u64) -> Self {
+                    bits as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic_value!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl AtomicValue for bool {
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits != 0
+    }
+}
+
+/// A slot holding a value inline.
+struct SmallSlot {
+    occupied: AtomicBool,
+    bits: AtomicU64,
+}
+
+/// A cache over a [`CacheStrategy`] whose values are [`AtomicValue`]s, which
+/// are stored inline in atomic cells rather than behind a lock.
+///
+/// Reads never block on other readers or writers; they return copies of the
+/// values. Unlike [`Cache`], two threads missing the same key at once may
+/// both load it, in which case the last load wins.
+///
+/// [`Cache`]: crate::Cache
+pub struct SmallValueCache<S, H = RandomState>
+where
+    S: CacheStrategy,
+{
+    slots: Box<[SmallSlot]>,
+    strategy: Mutex<S>,
+    hasher: H,
+}
+
+impl<S, H> SmallValueCache<S, H>
+where
+    S: CacheStrategy,
+    S::Key: Hash,
+    S::Val: AtomicValue,
+    H: BuildHasher,
+{
+    /// Constructs a new cache.
+    pub fn new<const CAPACITY: usize>(strategy: S) -> SmallValueCache<S, H>
+    where
+        H: Default,
+    {
+        Self::with_hasher::<CAPACITY>(strategy, H::default())
+    }
+
+    /// Constructs a new cache which maps keys to slots using the given hasher.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `CAPACITY` is zero.
+    pub fn with_hasher<const CAPACITY: usize>(strategy: S, hasher: H) -> SmallValueCache<S, H> {
+        assert!(CAPACITY > 0, "cache capacity must be non-zero");
+        let slots = (0..CAPACITY)
+            .map(|_| SmallSlot {
+                occupied: AtomicBool::new(false),
+                bits: AtomicU64::new(0),
+            })
+            .collect();
+        SmallValueCache {
+            slots,
+            strategy: Mutex::new(strategy),
+            hasher,
+        }
+    }
+
+    /// Returns the value by the given key, loading it if needed.
+    pub fn read(&self, key: &S::Key) -> Result<S::Val, LoadError<S::Err>> {
+        let hash = self.hasher.hash_one(key);
+        let i = slot_index(hash, self.slots.len());
+        let slot = &self.slots[i];
+
+        if let Some(val) = Self::get(key, slot) {
+            return Ok(val);
+        }
+        self.load(key, hash, i, Operation::Read)
+    }
+
+    /// Atomically replaces the value by the given key with `f(value)`,
+    /// loading it first if needed, and returns the new value.
+    ///
+    /// `f` may be called more than once if other threads update (or replace)
+    /// the same slot concurrently.
+    pub fn update<F>(&self, key: &S::Key, mut f: F) -> Result<S::Val, LoadError<S::Err>>
+    where
+        F: FnMut(S::Val) -> S::Val,
+    {
+        let hash = self.hasher.hash_one(key);
+        let i = slot_index(hash, self.slots.len());
+        let slot = &self.slots[i];
+
+        loop {
+            let cur = match Self::get(key, slot) {
+                Some(val) => val,
+                None => self.load(key, hash, i, Operation::Write)?,
+            };
+            let new = f(cur);
+            let res = slot.bits.compare_exchange(
+                cur.into_bits(),
+                new.into_bits(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+            if res.is_ok() {
+                return Ok(new);
+            }
+        }
+    }
+
+    /// Returns the slot's value if it corresponds to the given key.
+    fn get(key: &S::Key, slot: &SmallSlot) -> Option<S::Val> {
+        if !slot.occupied.load(Ordering::Acquire) {
+            return None;
+        }
+        let val = S::Val::from_bits(slot.bits.load(Ordering::Acquire));
+        matches!(S::try_validate(key, &val), Ok(Validation::Match)).then_some(val)
+    }
+
+    /// Loads the value for the given key and stores it in its slot.
+    fn load(
+        &self,
+        key: &S::Key,
+        hash: u64,
+        i: usize,
+        op: Operation,
+    ) -> Result<S::Val, LoadError<S::Err>> {
+        info!("loading small value...");
+        let val = {
+            let mut load_guard = self.strategy.lock().unwrap_or_else(PoisonError::into_inner);
+            load_guard.load(key).map_err(|err| {
+                let kind = S::classify_err(&err);
+                LoadError::new(err, kind, hash, i, op)
+            })?
+        };
+        let slot = &self.slots[i];
+        slot.bits.store(val.into_bits(), Ordering::Release);
+        slot.occupied.store(true, Ordering::Release);
+        Ok(val)
+    }
+
+    /// Returns the inner strategy.
+    pub fn into_strategy(self) -> S {
+        self.strategy
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::test_utils::TestHashBuilder;
+
+    use super::*;
+
+    /// Loads `key * 10`, so that values identify their keys.
+    #[derive(Default)]
+    struct Tens {
+        count: u32,
+    }
+
+    impl CacheStrategy for Tens {
+        type Key = u32;
+        type Val = u64;
+        type Err = ();
+
+        fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+            self.count += 1;
+            Ok(u64::from(*key) * 10)
+        }
+
+        fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+            *val / 10 != u64::from(*key)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cache capacity must be non-zero")]
+    fn test_zero_capacity() {
+        SmallValueCache::<Tens, TestHashBuilder>::new::<0>(Tens::default());
+    }
+
+    #[test]
+    fn test_read_and_collision() {
+        let c = SmallValueCache::<Tens, TestHashBuilder>::new::<4>(Tens::default());
+
+        assert_eq!(c.read(&1).unwrap(), 10);
+        assert_eq!(c.read(&1).unwrap(), 10);
+        assert_eq!(c.read(&5).unwrap(), 50);
+        assert_eq!(c.read(&1).unwrap(), 10);
+        assert_eq!(c.into_strategy().count, 3);
+    }
+
+    #[test]
+    fn test_concurrent_update() {
+        let c = SmallValueCache::<Tens, TestHashBuilder>::new::<4>(Tens::default());
+
+        thread::scope(|s| {
+            for _ in 0..3 {
+                s.spawn(|| {
+                    for _ in 0..3 {
+                        c.update(&1, |v| v + 1).unwrap();
+                    }
+                });
+            }
+        });
+        // Still within the `10..20` range, which identifies key `1`.
+        assert_eq!(c.read(&1).unwrap(), 19);
+        assert_eq!(c.into_strategy().count, 1);
+    }
+}
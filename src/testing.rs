@@ -0,0 +1,115 @@
+//! Utilities for writing deterministic tests against a [`Cache`].
+//!
+//! [`Cache`]: crate::Cache
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{BuildHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// A deterministic hasher which maps a chosen set of keys to the same slot.
+///
+/// Every key passed to [`CollidingHasher::collide_on`] hashes to zero, hence
+/// they all share slot `0` whatever the cache's capacity. Other keys hash
+/// deterministically (i.e., with the same result across runs), although, of
+/// course, some of them may map to slot `0` as well.
+///
+/// Clones share the set of colliding keys and the collision counter.
+#[derive(Clone, Default)]
+pub struct CollidingHasher {
+    colliding: Arc<HashSet<u64>>,
+    collisions: Arc<AtomicUsize>,
+}
+
+impl CollidingHasher {
+    /// Constructs a hasher under which all the given keys collide.
+    pub fn collide_on<K, I>(keys: I) -> CollidingHasher
+    where
+        K: Hash,
+        I: IntoIterator<Item = K>,
+    {
+        let colliding = keys
+            .into_iter()
+            .map(|key| {
+                let mut h = DefaultHasher::new();
+                key.hash(&mut h);
+                h.finish()
+            })
+            .collect();
+        CollidingHasher {
+            colliding: Arc::new(colliding),
+            collisions: Arc::default(),
+        }
+    }
+
+    /// Returns how many times one of the colliding keys has been hashed.
+    pub fn collisions(&self) -> usize {
+        self.collisions.load(Ordering::SeqCst)
+    }
+}
+
+impl BuildHasher for CollidingHasher {
+    type Hasher = CollidingHashState;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        CollidingHashState {
+            inner: DefaultHasher::new(),
+            builder: self.clone(),
+        }
+    }
+}
+
+/// The [`Hasher`] built by [`CollidingHasher`].
+pub struct CollidingHashState {
+    inner: DefaultHasher,
+    builder: CollidingHasher,
+}
+
+impl Hasher for CollidingHashState {
+    fn finish(&self) -> u64 {
+        let hash = self.inner.finish();
+        if self.builder.colliding.contains(&hash) {
+            self.builder.collisions.fetch_add(1, Ordering::SeqCst);
+            0
+        } else {
+            hash
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.write(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::TestStrategy, Cache};
+
+    use super::*;
+
+    #[test]
+    fn test_collide_on() {
+        let h = CollidingHasher::collide_on([2_u32, 3]);
+        let c = Cache::with_hasher::<64>(TestStrategy::default(), h.clone());
+
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+        assert_eq!(h.collisions(), 0);
+        assert_eq!(&*c.read(&2).unwrap(), "2two");
+        assert_eq!(&*c.read(&3).unwrap(), "3three");
+        assert_eq!(&*c.read(&2).unwrap(), "2two");
+        assert_eq!(c.clone_strategy().count(), 4);
+        assert!(h.collisions() >= 3);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let a = CollidingHasher::default();
+        let b = CollidingHasher::collide_on([1_u32]);
+        assert_eq!(a.hash_one(7_u32), b.hash_one(7_u32));
+        assert_eq!(b.hash_one(1_u32), 0);
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::RwLockWriteGuard;
+
+use crate::{guards::GuardToken, Cache, CacheStrategy, Entry};
+
+/// A slot reserved for a key which is being loaded outside of the cache,
+/// obtained from [`Cache::begin_load`].
+///
+/// The ticket holds the slot's write lock, so concurrent accesses to the
+/// slot wait for the load just like they would for a strategy load. The
+/// load is finished with [`Cache::complete_load`] or [`Cache::fail_load`];
+/// dropping the ticket instead abandons it, leaving the slot as it was.
+pub struct LoadTicket<'a, S, H>
+where
+    S: CacheStrategy,
+{
+    pub(crate) cache: &'a Cache<S, H>,
+    pub(crate) key: &'a S::Key,
+    pub(crate) hash: u64,
+    pub(crate) guard: RwLockWriteGuard<'a, Option<Entry<S::Val>>>,
+    pub(crate) _token: GuardToken<'a>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Barrier, thread};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache, Operation,
+    };
+
+    #[test]
+    fn test_two_phase_load() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let ticket = c.begin_load(&1).unwrap();
+        let err = c.fail_load(ticket, ());
+        assert_eq!((err.slot(), err.operation()), (1, Operation::ExternalLoad));
+
+        let began = Barrier::new(2);
+        thread::scope(|s| {
+            s.spawn(|| {
+                let ticket = c.begin_load(&1).unwrap();
+                began.wait();
+                assert_eq!(&*c.complete_load(ticket, "1ext".to_string()), "1ext");
+            });
+            began.wait();
+            // Waits for the external load rather than loading itself.
+            assert_eq!(&*c.read(&1).unwrap(), "1ext");
+        });
+
+        assert!(c.begin_load(&1).is_none());
+        assert_eq!(c.clone_strategy().count(), 0);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{error::Error, fmt, time::Duration};
+
+use crate::LoadError;
+
+/// An error when acquiring a value through [`Cache::read_timeout`] or
+/// [`Cache::write_timeout`].
+///
+/// [`Cache::read_timeout`]: crate::Cache::read_timeout
+/// [`Cache::write_timeout`]: crate::Cache::write_timeout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeoutError<E> {
+    /// The key's slot stayed locked for the whole timeout.
+    TimedOut { waited: Duration },
+    /// The strategy failed to load the key.
+    Load(LoadError<E>),
+}
+
+impl<E> From<LoadError<E>> for TimeoutError<E> {
+    fn from(err: LoadError<E>) -> Self {
+        TimeoutError::Load(err)
+    }
+}
+
+impl<E> fmt::Display for TimeoutError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutError::TimedOut { waited } => {
+                write!(f, "slot still locked after waiting for {waited:?}")
+            }
+            TimeoutError::Load(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E> Error for TimeoutError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TimeoutError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_lock_timeout() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+        let timeout = Duration::from_millis(5);
+
+        let w = c.write(&1).unwrap();
+        assert!(matches!(
+            c.read_timeout(&1, timeout),
+            Err(TimeoutError::TimedOut { waited }) if waited == timeout
+        ));
+        drop(w);
+
+        let r = c.read_timeout(&1, timeout).unwrap();
+        assert!(matches!(
+            c.write_timeout(&5, timeout),
+            Err(TimeoutError::TimedOut { .. })
+        ));
+        drop(r);
+        assert_eq!(&*c.write_timeout(&5, timeout).unwrap(), "5five");
+    }
+}
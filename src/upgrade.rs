@@ -0,0 +1,113 @@
+use std::{ops::Deref, sync::MutexGuard};
+
+use crate::{guards::GuardRegistry, GuardKind, ReadRef, Slot, WriteRef};
+
+/// A read-only shared view over a cache entry's value, which may be upgraded
+/// to a write guard, as obtained from [`Cache::read_upgradable`].
+///
+/// The standard `RwLock` can't be upgraded in place, so the slot is briefly
+/// unlocked during the upgrade. The guard holds the slot's upgrade token,
+/// which writers take before locking the slot, so that no writer may take it
+/// in between: the upgraded guard always holds the value which was read.
+/// Plain readers aren't held up by the token.
+///
+/// [`Cache::read_upgradable`]: crate::Cache::read_upgradable
+pub struct UpgradableReadRef<'a, V> {
+    read: ReadRef<'a, V>,
+    token: MutexGuard<'a, ()>,
+    slot: &'a Slot<V>,
+    index: usize,
+    guards: &'a GuardRegistry,
+}
+
+impl<'a, V> UpgradableReadRef<'a, V> {
+    pub(crate) fn new(
+        read: ReadRef<'a, V>,
+        token: MutexGuard<'a, ()>,
+        slot: &'a Slot<V>,
+        index: usize,
+        guards: &'a GuardRegistry,
+    ) -> UpgradableReadRef<'a, V> {
+        UpgradableReadRef {
+            read,
+            token,
+            slot,
+            index,
+            guards,
+        }
+    }
+
+    /// Upgrades the guard to a write guard over the same value.
+    pub fn upgrade(self) -> WriteRef<'a, V> {
+        drop(self.read);
+        let guard = self.slot.write_with_token(&self.token);
+        WriteRef::new(guard, self.guards.track(self.index, GuardKind::Write))
+    }
+
+    /// Converts the guard into a plain read guard, releasing the upgrade
+    /// token.
+    pub fn into_read_ref(self) -> ReadRef<'a, V> {
+        self.read
+    }
+}
+
+impl<V> Deref for UpgradableReadRef<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &Self::Target {
+        &self.read
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, thread};
+
+    use crate::{
+        test_utils::{TestHashBuilder, TestStrategy},
+        Cache,
+    };
+
+    #[test]
+    fn test_upgrade() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let data = c.read_upgradable(&1).unwrap();
+        assert_eq!(&*data, "1one");
+        data.upgrade().push_str("-mod");
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+        assert_eq!(c.clone_strategy().count(), 1);
+        c.check_invariants();
+
+        let data = c.read_upgradable(&1).unwrap();
+        assert_eq!(&*data.into_read_ref(), "1one-mod");
+        assert!(c.try_write(&1).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_upgrade_keeps_writers_out() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let data = c.read_upgradable(&1).unwrap();
+        assert!(c.try_write(&1).unwrap().is_none());
+        // Plain readers aren't held up.
+        assert_eq!(&*c.read(&1).unwrap(), "1one");
+
+        let (started, started_rx) = mpsc::channel();
+        thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                started.send(()).unwrap();
+                c.write(&1).unwrap().push_str("-w");
+            });
+            started_rx.recv().unwrap();
+            // Whenever the writer gets to the slot, it can only take it after
+            // the upgraded guard is dropped.
+            data.upgrade().push_str("-u");
+            writer.join().unwrap();
+        });
+        assert_eq!(&*c.read(&1).unwrap(), "1one-u-w");
+        c.check_invariants();
+    }
+}
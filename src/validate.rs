@@ -0,0 +1,139 @@
+use crate::LoadError;
+
+/// The outcome of [`Cache::validate_strategy`], identifying the sample keys
+/// by their index in the given slice.
+///
+/// [`Cache::validate_strategy`]: crate::Cache::validate_strategy
+#[derive(Debug, PartialEq, Eq)]
+pub struct StrategyReport<E> {
+    /// How many sample keys were loaded successfully.
+    pub loaded: usize,
+    /// The keys whose loaded value doesn't match the key itself, according
+    /// to [`CacheStrategy::validate`]. Such keys would be reloaded on every
+    /// access.
+    ///
+    /// [`CacheStrategy::validate`]: crate::CacheStrategy::validate
+    pub conflicting: Vec<usize>,
+    /// The keys which failed to load or to be validated, along with the
+    /// errors.
+    pub failed: Vec<(usize, LoadError<E>)>,
+}
+
+/// The outcome of checking a value against a key, as returned by
+/// [`CacheStrategy::validate`].
+///
+/// [`CacheStrategy::validate`]: crate::CacheStrategy::validate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Validation {
+    /// The value is the key's, and up to date.
+    Match,
+    /// The value was loaded for some other key, mapping to the same slot.
+    Conflict,
+    /// The value is the key's, but outdated.
+    Stale,
+}
+
+impl<E> StrategyReport<E> {
+    /// Returns whether no sample key conflicted with its loaded value. Load
+    /// failures don't count, since sample keys may legitimately not exist.
+    pub fn is_consistent(&self) -> bool {
+        self.conflicting.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::{
+        test_utils::{self, TestHashBuilder, TestStrategy, Tweaked, INVERTED},
+        Cache, CacheStrategy, Operation,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_validate_strategy() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let report = c.validate_strategy(&[1, 0, 2, 9]);
+        assert_eq!(report.loaded, 3);
+        assert_eq!(report.conflicting, [3]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, 1);
+        assert_eq!(report.failed[0].1.operation(), Operation::Validate);
+        assert!(!report.is_consistent());
+
+        // Sample loads don't go into the slots.
+        assert_eq!(c.map_values(|_, _| ()), 0);
+    }
+
+    #[test]
+    fn test_validate_strategy_inverted() {
+        let c = Cache::<_, TestHashBuilder>::new::<4>(Tweaked::<INVERTED>::default());
+        assert_eq!(c.validate_strategy(&[1, 2]).conflicting, [0, 1]);
+    }
+
+    #[test]
+    fn test_stale_validation() {
+        /// The version values are loaded at, as bumped by the backing store.
+        static VERSION: AtomicU32 = AtomicU32::new(0);
+
+        #[derive(Clone, Default)]
+        struct Versioned {
+            reloads: u32,
+        }
+
+        impl CacheStrategy for Versioned {
+            type Key = u32;
+            type Val = (u32, u32);
+            type Err = ();
+
+            fn load(&mut self, key: &Self::Key) -> Result<Self::Val, Self::Err> {
+                Ok((*key, VERSION.load(Ordering::SeqCst)))
+            }
+
+            fn match_kv(key: &Self::Key, val: &Self::Val) -> bool {
+                *key != val.0
+            }
+
+            fn validate(key: &Self::Key, val: &Self::Val) -> Validation {
+                if *key != val.0 {
+                    Validation::Conflict
+                } else if val.1 != VERSION.load(Ordering::SeqCst) {
+                    Validation::Stale
+                } else {
+                    Validation::Match
+                }
+            }
+
+            fn on_divergence(&mut self, _: &Self::Key, _: &Self::Val, _: &Self::Val) {
+                self.reloads += 1;
+            }
+        }
+
+        let c = Cache::<Versioned, TestHashBuilder>::new::<4>(Versioned::default());
+        assert_eq!(*c.read(&1).unwrap(), (1, 0));
+        assert_eq!(*c.read(&1).unwrap(), (1, 0));
+        VERSION.store(1, Ordering::SeqCst);
+        assert_eq!(*c.read(&1).unwrap(), (1, 1));
+        assert_eq!(c.clone_strategy().reloads, 1);
+    }
+
+    #[test]
+    fn test_fallible_validation() {
+        let c = Cache::<Tweaked, TestHashBuilder>::new::<4>(Tweaked::default());
+        assert_eq!(*c.read(&2).unwrap(), "2two");
+        // Validation fails while the metadata it checks is unreachable.
+        test_utils::fail_validation(true);
+        let err = c.read(&2).err().unwrap();
+        assert_eq!(err.operation(), Operation::Read);
+        assert!(c.write(&2).is_err());
+        // Infallible operations treat the value as missing.
+        assert!(!c.contains(&2));
+        assert_eq!(c.validate_strategy(&[2]).failed.len(), 1);
+        test_utils::fail_validation(false);
+        assert_eq!(*c.read(&2).unwrap(), "2two");
+    }
+}
@@ -1,33 +1,127 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
+    error::Error,
+    fmt,
     hash::{BuildHasher, Hash},
     mem,
+    panic::{self, AssertUnwindSafe},
+    thread,
 };
 
-use crate::{Cache, CacheStrategy, WriteRef};
+use tracing::warn;
+
+use crate::{misuse, Cache, CacheStrategy, LoadError, MisusePolicy, Operation, WriteRef};
 
 /// A write batch represents a collection of write cache entries are grouped to
 /// be flushed together.
 ///
-/// The `flush_all` method must be called before the `WriteBatch` instance is
-/// dropped. Otherwise, a panic will be raised when dropping it.
+/// The `flush_all` method (or one of its alternatives) must be called before
+/// the `WriteBatch` instance is dropped. Otherwise, dropping it is reported as
+/// misuse (see [`MisusePolicy`]), and its entries are released unflushed.
+/// Batches dropped while the thread is panicking aren't reported.
+///
+/// [`MisusePolicy`]: crate::MisusePolicy
 pub struct WriteBatch<'c, S, H>
 where
     S: CacheStrategy,
 {
     cache: &'c Cache<S, H>,
-    entries: HashMap<S::Key, WriteRef<'c, S::Val>>,
+    entries: HashMap<S::Key, WriteRef<'c, S::Val>, H>,
+    config: BatchConfig,
+}
+
+/// What a [`WriteBatch`] does when a key is written more than once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateWrites {
+    /// The writes are applied to the same entry, which is flushed once.
+    #[default]
+    Merge,
+    /// Like `Merge`, but a warning is emitted.
+    Warn,
+    /// The second write is reported as misuse (see [`MisusePolicy`]), for
+    /// callers which consider it a bug. If the policy doesn't panic, the
+    /// writes are then merged.
+    Misuse,
+    /// The second write fails with [`BatchWriteError::DuplicateKey`], leaving
+    /// the entry as the first write left it.
+    Error,
+}
+
+/// An error when writing through [`WriteBatch::write`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchWriteError<E> {
+    /// The key was already written in the batch, and duplicate writes are set
+    /// to [`DuplicateWrites::Error`].
+    DuplicateKey { key_hash: u64 },
+    /// The strategy failed to load the key.
+    Load(LoadError<E>),
+}
+
+impl<E> From<LoadError<E>> for BatchWriteError<E> {
+    fn from(err: LoadError<E>) -> Self {
+        BatchWriteError::Load(err)
+    }
+}
+
+impl<E> fmt::Display for BatchWriteError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchWriteError::DuplicateKey { key_hash } => {
+                write!(
+                    f,
+                    "key with hash {key_hash:#018x} written twice in the same batch"
+                )
+            }
+            BatchWriteError::Load(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E> Error for BatchWriteError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BatchWriteError::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Which entries [`WriteBatch::flush`] flushes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FlushMode {
+    /// Every entry is flushed, as by [`WriteBatch::flush_all`].
+    #[default]
+    All,
+    /// Only modified entries are flushed, as by
+    /// [`WriteBatch::flush_modified_only`].
+    ModifiedOnly,
+}
+
+/// The settings a [`WriteBatch`] starts with, as configured for its cache.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BatchConfig {
+    pub(crate) duplicates: DuplicateWrites,
+    pub(crate) flush_mode: FlushMode,
 }
 
 impl<'c, S, H> WriteBatch<'c, S, H>
 where
     S: CacheStrategy,
+    H: Clone,
 {
-    /// Constructs a new  `WriteBatch`.
-    pub(crate) fn new(cache: &'c Cache<S, H>) -> WriteBatch<'c, S, H> {
+    /// Constructs a new `WriteBatch` with room for `capacity` entries, whose
+    /// keys are hashed with (a clone of) the cache's hasher.
+    pub(crate) fn new(cache: &'c Cache<S, H>, capacity: usize) -> WriteBatch<'c, S, H> {
         Self {
             cache,
-            entries: HashMap::with_capacity(8),
+            entries: HashMap::with_capacity_and_hasher(capacity, cache.hasher.clone()),
+            config: cache.batch_config,
         }
     }
 }
@@ -36,21 +130,54 @@ impl<'c, S, H> WriteBatch<'c, S, H>
 where
     S: CacheStrategy,
     S::Key: Hash + Eq + Copy,
-    H: BuildHasher + Default,
+    H: BuildHasher + Clone,
 {
+    /// Sets what is done when a key is written more than once. Writes are
+    /// merged unless the cache was built with another policy (see
+    /// [`CacheBuilder::duplicate_writes`]).
+    ///
+    /// [`CacheBuilder::duplicate_writes`]: crate::CacheBuilder::duplicate_writes
+    pub fn set_duplicate_writes(&mut self, policy: DuplicateWrites) {
+        self.config.duplicates = policy;
+    }
+
+    /// Sets which entries [`WriteBatch::flush`] flushes. Every entry is
+    /// flushed unless the cache was built with another mode (see
+    /// [`CacheBuilder::flush_mode`]).
+    ///
+    /// [`CacheBuilder::flush_mode`]: crate::CacheBuilder::flush_mode
+    pub fn set_flush_mode(&mut self, mode: FlushMode) {
+        self.config.flush_mode = mode;
+    }
+
     /// Creates a scope on which the value corresponding to the given key may be
     /// modified.
-    pub fn write<F, R>(&mut self, key: &S::Key, f: F) -> Result<R, S::Err>
+    ///
+    /// The scope gets the entry's [`WriteRef`], which only records the value
+    /// as modified if it is mutably dereferenced (see
+    /// [`WriteRef::was_modified`]).
+    ///
+    /// Fails with [`BatchWriteError::DuplicateKey`] if the key was already
+    /// written and duplicate writes are set to [`DuplicateWrites::Error`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key was already written, duplicate writes are set to
+    /// [`DuplicateWrites::Misuse`] and misuse panics.
+    pub fn write<F, R>(&mut self, key: &S::Key, f: F) -> Result<R, BatchWriteError<S::Err>>
     where
-        F: for<'a> Fn(&'a mut S::Val) -> R,
+        F: FnOnce(&mut WriteRef<'c, S::Val>) -> R,
     {
+        if self.entries.contains_key(key) {
+            self.report_duplicate(key)?;
+        }
         match self.entries.entry(*key) {
             Entry::Occupied(mut entry) => {
                 let val = entry.get_mut();
                 Ok(f(val))
             }
             Entry::Vacant(entry) => {
-                let guard = self.cache.write(&key)?;
+                let guard = self.cache.write_for(key, Operation::Batch)?;
                 let guard_ref = entry.insert(guard);
                 Ok(f(guard_ref))
             }
@@ -61,15 +188,118 @@ where
     ///
     /// Callers must ensure previous writes are reverted in case of any
     /// posterior errors in the batch sequence.
-    pub fn flush_all<F, E>(mut self, mut f: F) -> Result<(), E>
+    ///
+    /// If the function panics, the slot of the entry it was given is poisoned,
+    /// but the remaining entries are released cleanly. Which entries were
+    /// flushed is then recorded (see [`Cache::take_flush_panic`]) before the
+    /// panic is resumed.
+    pub fn flush_all<F, E>(self, f: F) -> Result<(), E>
+    where
+        F: FnMut(WriteRef<'c, S::Val>) -> Result<(), E>,
+    {
+        self.flush_entries(f, false)
+    }
+
+    /// Like [`WriteBatch::flush_all`], but only flushes the entries which were
+    /// modified. The remaining ones are simply released.
+    pub fn flush_modified_only<F, E>(self, f: F) -> Result<(), E>
+    where
+        F: FnMut(WriteRef<'c, S::Val>) -> Result<(), E>,
+    {
+        self.flush_entries(f, true)
+    }
+
+    /// Flushes the entries selected by the batch's [`FlushMode`], as
+    /// [`WriteBatch::flush_all`] or [`WriteBatch::flush_modified_only`] does.
+    pub fn flush<F, E>(self, f: F) -> Result<(), E>
+    where
+        F: FnMut(WriteRef<'c, S::Val>) -> Result<(), E>,
+    {
+        let modified_only = self.config.flush_mode == FlushMode::ModifiedOnly;
+        self.flush_entries(f, modified_only)
+    }
+
+    fn report_duplicate(&mut self, key: &S::Key) -> Result<(), BatchWriteError<S::Err>> {
+        let hash = self.cache.hash(key);
+        match self.config.duplicates {
+            DuplicateWrites::Merge => {}
+            DuplicateWrites::Warn => {
+                warn!("key with hash {hash:#018x} written twice in the same batch");
+            }
+            DuplicateWrites::Misuse => {
+                let policy = MisusePolicy::current();
+                if policy == MisusePolicy::Panic {
+                    // Release the batch's guards before panicking, so that
+                    // their slots don't get poisoned.
+                    drop(self.take_entries());
+                }
+                misuse::report_with(
+                    policy,
+                    format_args!("key with hash {hash:#018x} written twice in the same batch"),
+                );
+            }
+            DuplicateWrites::Error => {
+                return Err(BatchWriteError::DuplicateKey { key_hash: hash });
+            }
+        }
+        Ok(())
+    }
+
+    fn take_entries(&mut self) -> HashMap<S::Key, WriteRef<'c, S::Val>, H> {
+        let hasher = self.entries.hasher().clone();
+        mem::replace(&mut self.entries, HashMap::with_hasher(hasher))
+    }
+
+    fn flush_entries<F, E>(mut self, mut f: F, modified_only: bool) -> Result<(), E>
     where
         F: FnMut(WriteRef<'c, S::Val>) -> Result<(), E>,
     {
-        for entry in mem::take(&mut self.entries).into_values() {
-            f(entry)?;
+        let cache = self.cache;
+        let mut flushed = Vec::new();
+        let mut entries = self.take_entries().into_iter();
+        while let Some((key, entry)) = entries.next() {
+            if modified_only && !entry.was_modified() {
+                continue;
+            }
+            let hash = cache.hash(&key);
+            match panic::catch_unwind(AssertUnwindSafe(|| f(entry))) {
+                Ok(res) => res?,
+                Err(payload) => {
+                    // The remaining guards are dropped here, outside of the
+                    // panic, so that their slots don't get poisoned.
+                    let unflushed = entries.map(|(key, _)| cache.hash(&key)).collect();
+                    cache.record_flush_panic(FlushPanic {
+                        flushed,
+                        panicked: hash,
+                        unflushed,
+                    });
+                    panic::resume_unwind(payload);
+                }
+            }
+            flushed.push(hash);
         }
         Ok(())
     }
+
+    /// Takes over the batch's entries along with their keys, in no particular
+    /// order, for callers which flush them in several steps.
+    ///
+    /// This consumes the batch, so the returned guards may be dropped freely.
+    pub fn into_entries(mut self) -> Vec<(S::Key, WriteRef<'c, S::Val>)> {
+        self.take_entries().into_iter().collect()
+    }
+}
+
+/// A record of a flush function which panicked, identifying the batch's
+/// entries by the hash of their keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlushPanic {
+    /// The entries which were flushed before the panic.
+    pub flushed: Vec<u64>,
+    /// The entry the function panicked on, whose slot was poisoned.
+    pub panicked: u64,
+    /// The entries which were released without being flushed.
+    pub unflushed: Vec<u64>,
 }
 
 impl<'c, S, H> Drop for WriteBatch<'c, S, H>
@@ -77,21 +307,29 @@ where
     S: CacheStrategy,
 {
     fn drop(&mut self) {
-        if self.entries.len() != 0 {
-            panic!("dropped `WriteBatch` without calling `flush_all`")
+        // A batch dropped while unwinding is the panic's fallout rather than
+        // misuse, and panicking again would abort the process.
+        if !self.entries.is_empty() && !thread::panicking() {
+            misuse::report(format_args!("dropped `WriteBatch` without flushing it"));
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::{
+        collections::HashSet,
+        panic::{self, AssertUnwindSafe},
+    };
 
     use crate::{
+        misuse,
         test_utils::{TestHashBuilder, TestStrategy},
-        Cache,
+        Cache, DuplicateWrites, MisusePolicy,
     };
 
+    use super::*;
+
     #[test]
     fn test_grouped_flush() {
         let s = TestStrategy::default();
@@ -130,4 +368,152 @@ mod tests {
             assert_eq!(c.clone_strategy().count(), 2);
         }
     }
+
+    #[test]
+    fn test_flush_modified_only() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut wb = c.write_batch();
+        wb.write(&1, |val| val.push_str("-mod")).unwrap();
+        let len = wb.write(&2, |val| val.len()).unwrap();
+        assert_eq!(len, 4);
+
+        let mut flushed = Vec::new();
+        wb.flush_modified_only(|val| {
+            flushed.push(val.clone());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(flushed, ["1one-mod"]);
+
+        // The unmodified entry must have been released as well.
+        c.assert_no_outstanding_guards();
+    }
+
+    #[test]
+    fn test_flush_panic() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut wb = c.write_batch();
+        for key in [1, 2, 3] {
+            wb.write(&key, |val| val.push_str("-mod")).unwrap();
+        }
+        let mut calls = 0;
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            wb.flush_all(|val| {
+                calls += 1;
+                assert_ne!(calls, 2, "flush failed on {}", *val);
+                Ok::<_, ()>(())
+            })
+        }));
+        assert!(res.is_err());
+
+        let record = c.take_flush_panic().unwrap();
+        assert!(c.take_flush_panic().is_none());
+        assert_eq!((record.flushed.len(), record.unflushed.len()), (1, 1));
+        let mut all = [record.flushed, vec![record.panicked], record.unflushed].concat();
+        all.sort();
+        assert_eq!(all, [1, 2, 3]);
+
+        // Only the slot of the entry the function panicked on is poisoned.
+        for hash in all {
+            let slot = c.slot_at(c.slot_for_hash(hash));
+            assert_eq!(slot.lock.is_poisoned(), hash == record.panicked);
+        }
+    }
+
+    #[test]
+    fn test_into_entries() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut wb = c.write_batch_with_capacity(2);
+        wb.write(&1, |val| val.push_str("-mod")).unwrap();
+        wb.write(&2, |_| ()).unwrap();
+
+        let mut entries = wb.into_entries();
+        entries.sort_by_key(|(key, _)| *key);
+        let entries: Vec<_> = entries
+            .into_iter()
+            .map(|(key, val)| (key, val.clone(), val.was_modified()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (1, "1one-mod".to_string(), true),
+                (2, "2two".to_string(), false)
+            ]
+        );
+        c.assert_no_outstanding_guards();
+    }
+
+    #[test]
+    fn test_duplicate_writes_misuse() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        misuse::with_policy(MisusePolicy::Panic, || {
+            let mut wb = c.write_batch();
+            wb.set_duplicate_writes(DuplicateWrites::Misuse);
+            wb.write(&1, |val| val.push_str("-mod")).unwrap();
+            wb.write(&2, |val| val.push_str("-mod")).unwrap();
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                wb.write(&1, |val| val.push_str("-mod")).unwrap();
+            }));
+            assert!(res.is_err());
+        });
+
+        // None of the batch's slots is poisoned.
+        c.check_invariants();
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod");
+
+        // Unless misuse panics, the writes are merged.
+        misuse::with_policy(MisusePolicy::Log, || {
+            let mut wb = c.write_batch();
+            wb.set_duplicate_writes(DuplicateWrites::Misuse);
+            wb.write(&1, |val| val.push('!')).unwrap();
+            wb.write(&1, |val| val.push('!')).unwrap();
+            wb.flush_all(|_| Ok::<_, ()>(())).unwrap();
+        });
+        assert_eq!(&*c.read(&1).unwrap(), "1one-mod!!");
+    }
+
+    #[test]
+    fn test_drop_while_panicking() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        misuse::with_policy(MisusePolicy::Panic, || {
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                let mut wb = c.write_batch();
+                wb.write(&1, |val| val.push_str("-mod")).unwrap();
+                panic!("caller failed with the batch alive");
+            }));
+            assert!(res.is_err());
+        });
+        c.assert_no_outstanding_guards();
+    }
+
+    #[test]
+    fn test_duplicate_writes_error() {
+        let s = TestStrategy::default();
+        let c = Cache::<TestStrategy, TestHashBuilder>::new::<4>(s);
+
+        let mut wb = c.write_batch();
+        wb.set_duplicate_writes(DuplicateWrites::Error);
+        wb.write(&1, |val| val.push_str("-mod")).unwrap();
+        let err = wb.write(&1, |val| val.push('!')).unwrap_err();
+        assert_eq!(err, BatchWriteError::DuplicateKey { key_hash: 1 });
+
+        // The first write's entry is flushed as it left it.
+        let mut flushed = Vec::new();
+        wb.flush_all(|val| {
+            flushed.push(val.clone());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(flushed, ["1one-mod"]);
+    }
 }